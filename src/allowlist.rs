@@ -0,0 +1,149 @@
+// allowlist.rs - Известные легитимные сборки (подавление ложных срабатываний)
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::detector::JarContext;
+
+// Поставляемый список вшит в exe, локальный лежит рядом с exe
+const SHIPPED_ALLOWLIST: &str = include_str!("../data/allowlist.json");
+const ALLOWLIST_FILE_NAMES: [&str; 2] = ["allowlist.toml", "allowlist.json"];
+
+// Запись срабатывает по SHA-256 файла либо по паре mod id + версия (обе обязательны)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowEntry {
+    #[serde(default)]
+    pub sha256: Option<String>,
+    #[serde(default)]
+    pub mod_id: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    pub reason: String,
+}
+
+impl AllowEntry {
+    fn matches(&self, ctx: &JarContext) -> bool {
+        if let Some(ref sha256) = self.sha256 {
+            return sha256.trim().eq_ignore_ascii_case(&ctx.hashes.sha256);
+        }
+
+        match (&self.mod_id, &self.version) {
+            (Some(mod_id), Some(version)) => ctx.metadata.mods.iter().any(|m| {
+                m.id.eq_ignore_ascii_case(mod_id) && m.version.as_deref() == Some(version.as_str())
+            }),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Allowlist {
+    #[serde(default)]
+    pub entries: Vec<AllowEntry>,
+}
+
+impl Allowlist {
+    pub fn shipped() -> Self {
+        serde_json::from_str(SHIPPED_ALLOWLIST).unwrap_or_default()
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let is_toml = path.extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+
+        if is_toml {
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+    }
+
+    // Поставляемый список + локальный файл рядом с exe (если есть)
+    pub fn load_default() -> (Self, Option<String>) {
+        let mut allowlist = Self::shipped();
+        let Some(path) = Self::default_path() else {
+            return (allowlist, None);
+        };
+
+        match Self::from_file(&path) {
+            Ok(local) => {
+                allowlist.entries.extend(local.entries);
+                (allowlist, None)
+            }
+            Err(e) => (allowlist, Some(e)),
+        }
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+        ALLOWLIST_FILE_NAMES.iter()
+            .map(|name| exe_dir.join(name))
+            .find(|p| p.is_file())
+    }
+
+    // Причина подавления для первой подходящей записи
+    pub fn find(&self, ctx: &JarContext) -> Option<&AllowEntry> {
+        self.entries.iter().find(|entry| entry.matches(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::CheatDetector;
+    use crate::hashes::FileHashes;
+    use crate::testutil::jar;
+
+    fn freecam_jar() -> Vec<u8> {
+        jar(&[("fabric.mod.json", br#"{"schemaVersion": 1, "id": "freecam", "version": "1.2.0"}"#)])
+    }
+
+    fn entry(sha256: Option<&str>, mod_id: Option<&str>, version: Option<&str>) -> Allowlist {
+        Allowlist {
+            entries: vec![AllowEntry {
+                sha256: sha256.map(str::to_string),
+                mod_id: mod_id.map(str::to_string),
+                version: version.map(str::to_string),
+                reason: "сборка сервера".to_string(),
+            }],
+        }
+    }
+
+    // Единственная находка по jar и причина подавления, если есть
+    fn scan(name: &str, allowlist: Allowlist) -> (String, Option<String>) {
+        let path = std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name));
+        std::fs::write(&path, freecam_jar()).unwrap();
+        let scan = CheatDetector::new().with_allowlist(allowlist).check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(scan.threats.len(), 1);
+        let threat = scan.threats.into_iter().next().unwrap();
+        (threat.cheat_type, threat.suppressed)
+    }
+
+    #[test]
+    fn sha256_entry_suppresses_match() {
+        let sha256 = FileHashes::from_bytes(&freecam_jar()).sha256.to_uppercase();
+        let (cheat, suppressed) = scan("allow-sha.jar", entry(Some(&sha256), None, None));
+        assert_eq!(cheat, "Freecam");
+        assert_eq!(suppressed.as_deref(), Some("сборка сервера"));
+    }
+
+    #[test]
+    fn mod_id_and_version_entry_suppresses_match() {
+        let (cheat, suppressed) = scan("allow-id.jar", entry(None, Some("FreeCam"), Some("1.2.0")));
+        assert_eq!(cheat, "Freecam");
+        assert_eq!(suppressed.as_deref(), Some("сборка сервера"));
+    }
+
+    #[test]
+    fn other_version_is_not_suppressed() {
+        let (_, suppressed) = scan("allow-version.jar", entry(None, Some("freecam"), Some("1.2.1")));
+        assert_eq!(suppressed, None);
+        // Без версии запись не срабатывает вовсе
+        let (_, suppressed) = scan("allow-no-version.jar", entry(None, Some("freecam"), None));
+        assert_eq!(suppressed, None);
+    }
+}
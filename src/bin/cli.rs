@@ -0,0 +1,406 @@
+// cli.rs - Консольный сканер без GUI
+//
+// Коды выхода:
+//   0 - угроз нет, проверено всё
+//   1 - найдены угрозы или подозрительные файлы (важнее ошибок)
+//   2 - проверка неполная: ошибки чтения, повреждённые архивы, лимиты
+//   3 - неверные аргументы (и -a без единого инстанса), не загружается база или не записан отчёт
+// В режиме --verify: 0 - подпись верна и ключ доверенный, 1 - отчёт изменён или подпись
+// испорчена, 2 - подписи нет или ключ неизвестен, 3 - файл не читается
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Instant, SystemTime};
+use serde::Serialize;
+use sbcheats_scanner::launchers;
+use sbcheats_scanner::{
+    signing, Allowlist, CheatDetector, JarRecord, ReportFormat, ReportSigner, ScanError, ScanMessage,
+    ScanReport, ScanStats, Scanner, ThreatResult, TrustedKeys, Verdict, Verification,
+};
+
+const EXIT_CLEAN: u8 = 0;
+const EXIT_THREATS: u8 = 1;
+const EXIT_INCOMPLETE: u8 = 2;
+const EXIT_USAGE: u8 = 3;
+
+const USAGE: &str = "\
+Использование: sbcheats-cli [ОПЦИИ] <ПУТЬ>...
+       sbcheats-cli [ОПЦИИ] --all-instances
+       sbcheats-cli --verify <ОТЧЁТ> [--trusted <ФАЙЛ>]
+       sbcheats-cli --gen-key <ФАЙЛ>
+
+Опции:
+  -a, --all-instances   Проверить папки mods всех найденных лаунчеров и инстансов
+      --launch          Проверить -javaagent / --tweakClass из окружения и профилей, versions/,
+                        libraries/ и логи инстансов, к которым относятся пути (с -a включено)
+  -t, --threads <N>     Число потоков (по умолчанию по числу ядер, 2..8)
+  -d, --db <ФАЙЛ>       База сигнатур JSON/TOML (по умолчанию signatures.* рядом с exe)
+  -f, --format <ФОРМАТ> text | json | jsonl (по умолчанию text)
+  -o, --output <ФАЙЛ>   Сохранить полный отчёт: .json, .csv или .html
+  -c, --challenge <ФРАЗА> Фраза модератора: после проверки выводится код проверки
+  -k, --key <ФАЙЛ>      Ключ подписи отчётов (по умолчанию report_signing.key рядом с exe)
+      --verify <ОТЧЁТ>  Проверить подпись отчёта .json/.html
+      --trusted <ФАЙЛ>  Доверенные открытые ключи (по умолчанию report_trusted.keys рядом с exe)
+      --gen-key <ФАЙЛ>  Создать ключ подписи и вывести открытый ключ
+  -h, --help            Эта справка
+
+Коды выхода: 0 - чисто, 1 - найдены угрозы, 2 - проверка неполная, 3 - ошибка запуска или записи отчёта
+С --verify: 0 - подпись верна, 1 - отчёт изменён, 2 - нет подписи или ключ не доверенный";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    JsonLines,
+}
+
+struct Options {
+    paths: Vec<PathBuf>,
+    all_instances: bool,
+    launch: bool,
+    threads: usize,
+    database: Option<PathBuf>,
+    format: Format,
+    output: Option<(PathBuf, ReportFormat)>,
+    challenge: Option<String>,
+    key: Option<PathBuf>,
+    trusted: Option<PathBuf>,
+    verify: Option<PathBuf>,
+    gen_key: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        paths: Vec::new(),
+        all_instances: false,
+        launch: false,
+        threads: num_cpus::get().clamp(2, 8),
+        database: None,
+        format: Format::Text,
+        output: None,
+        challenge: None,
+        key: None,
+        trusted: None,
+        verify: None,
+        gen_key: None,
+    };
+
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .map(|v| v.to_string_lossy().into_owned())
+                .ok_or_else(|| format!("{}: нужно значение", name))
+        };
+        match arg.to_string_lossy().as_ref() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(EXIT_CLEAN.into());
+            }
+            "-a" | "--all-instances" => options.all_instances = true,
+            "--launch" => options.launch = true,
+            "-t" | "--threads" => {
+                let threads = value("--threads")?;
+                options.threads = threads.parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| format!("--threads: неверное число \"{}\"", threads))?;
+            }
+            "-d" | "--db" => options.database = Some(PathBuf::from(value("--db")?)),
+            "-f" | "--format" => {
+                options.format = match value("--format")?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "jsonl" => Format::JsonLines,
+                    other => return Err(format!("--format: неизвестный формат \"{}\"", other)),
+                };
+            }
+            "-o" | "--output" => {
+                let path = PathBuf::from(value("--output")?);
+                let format = ReportFormat::from_path(&path)
+                    .ok_or_else(|| format!("--output: нужен файл .json, .csv или .html, а не \"{}\"", path.display()))?;
+                options.output = Some((path, format));
+            }
+            "-c" | "--challenge" => options.challenge = Some(value("--challenge")?),
+            "-k" | "--key" => options.key = Some(PathBuf::from(value("--key")?)),
+            "--trusted" => options.trusted = Some(PathBuf::from(value("--trusted")?)),
+            "--verify" => options.verify = Some(PathBuf::from(value("--verify")?)),
+            "--gen-key" => options.gen_key = Some(PathBuf::from(value("--gen-key")?)),
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("неизвестная опция {}", flag));
+            }
+            _ => options.paths.push(PathBuf::from(arg)),
+        }
+    }
+
+    let scan_mode = options.verify.is_none() && options.gen_key.is_none();
+    if scan_mode && options.paths.is_empty() && !options.all_instances {
+        return Err("не указан путь".to_string());
+    }
+    Ok(options)
+}
+
+// Явно указанная база обязана загрузиться; база по умолчанию - как в GUI:
+// нет файла - встроенная, ошибка в файле - встроенная с предупреждением
+fn load_detector(database: Option<&PathBuf>) -> Result<CheatDetector, String> {
+    let detector = match database {
+        Some(path) => CheatDetector::from_file(path)?,
+        None => {
+            let path = CheatDetector::default_database_path();
+            if path.is_file() {
+                CheatDetector::from_file(&path).unwrap_or_else(|e| {
+                    eprintln!("Ошибка базы: {} (используется встроенная)", e);
+                    CheatDetector::new()
+                })
+            } else {
+                CheatDetector::new()
+            }
+        }
+    };
+
+    let (allowlist, allowlist_error) = Allowlist::load_default();
+    if let Some(e) = allowlist_error {
+        eprintln!("Ошибка allowlist: {}", e);
+    }
+    Ok(detector.with_allowlist(allowlist))
+}
+
+// Как с базой: явный ключ обязан загрузиться, ключ по умолчанию - необязателен
+fn load_signer(key: Option<&PathBuf>) -> Result<Option<ReportSigner>, String> {
+    match key {
+        Some(path) => ReportSigner::from_file(path).map(Some),
+        None => match ReportSigner::load_default() {
+            Some(Ok(signer)) => Ok(Some(signer)),
+            Some(Err(e)) => {
+                eprintln!("Ошибка ключа подписи: {} (отчёт без подписи)", e);
+                Ok(None)
+            }
+            None => Ok(None),
+        },
+    }
+}
+
+fn generate_key(path: &Path) -> ExitCode {
+    if path.exists() {
+        eprintln!("Ошибка: {} уже существует", path.display());
+        return ExitCode::from(EXIT_USAGE);
+    }
+    match ReportSigner::generate().and_then(|signer| signer.save(path).map(|_| signer)) {
+        Ok(signer) => {
+            println!("Ключ подписи: {}", path.display());
+            println!("Открытый ключ (добавить в report_trusted.keys модераторов):");
+            println!("{}", signer.public_key());
+            ExitCode::from(EXIT_CLEAN)
+        }
+        Err(e) => {
+            eprintln!("Ошибка: {}", e);
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
+
+fn verify_report(path: &Path, trusted: Option<&PathBuf>) -> ExitCode {
+    let trusted = match trusted {
+        Some(file) => TrustedKeys::from_file(file),
+        None => {
+            let (keys, error) = TrustedKeys::load_default();
+            error.map_or(Ok(keys), Err)
+        }
+    };
+    // Ключ своей сборки не доверенный автоматически: такой же лежит у каждого игрока
+    let trusted = match trusted {
+        Ok(keys) => keys,
+        Err(e) => {
+            eprintln!("Ошибка списка ключей: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let (report, verification) = match signing::verify_file(path, &trusted) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Ошибка: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    println!("{}", verification.label());
+    println!(
+        "Пути: {} | Начало: {} | Проверено: {}/{} | Угроз: {} | Подозрительных: {} | Ошибок: {}",
+        report.paths.join(", "), sbcheats_scanner::report::format_timestamp(report.started_at),
+        report.stats.checked, report.stats.total, report.stats.found, report.stats.suspicious,
+        report.errors.len(),
+    );
+    println!("Итог отчёта: {}", report.verdict().label());
+    if let Some(code) = report.verification_code() {
+        println!("Фраза: \"{}\" | Код проверки: {}", report.challenge.as_deref().unwrap_or(""), code);
+    }
+    ExitCode::from(match verification {
+        Verification::Trusted => EXIT_CLEAN,
+        Verification::Altered | Verification::Invalid(_) => EXIT_THREATS,
+        Verification::Unsigned | Verification::UnknownKey(_) => EXIT_INCOMPLETE,
+    })
+}
+
+// Одна строка JSON Lines
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Event<'a> {
+    Threat(&'a ThreatResult),
+    Error(&'a ScanError),
+    Jar(&'a JarRecord),
+    Summary {
+        stats: &'a ScanStats,
+        exit_code: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        verification_code: Option<String>,
+    },
+}
+
+fn print_threat(threat: &ThreatResult) {
+    match threat.suppressed {
+        Some(ref reason) => {
+            println!("[Подавлено] {} - {} ({})", threat.path, threat.cheat_type, reason);
+        }
+        None => {
+            println!("[{}] {} - {} ({}%)", threat.kind.label(), threat.path, threat.cheat_type, threat.confidence);
+            for detail in &threat.details {
+                println!("    {}", detail);
+            }
+            if !threat.hashes.is_empty() {
+                println!("    SHA-256: {}", threat.hashes.sha256);
+            }
+        }
+    }
+}
+
+fn exit_code(verdict: Verdict) -> u8 {
+    match verdict {
+        Verdict::Clean => EXIT_CLEAN,
+        Verdict::Threats => EXIT_THREATS,
+        Verdict::Incomplete => EXIT_INCOMPLETE,
+    }
+}
+
+fn main() -> ExitCode {
+    let mut options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("Ошибка: {}\n\n{}", e, USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    if let Some(ref path) = options.gen_key {
+        return generate_key(path);
+    }
+    if let Some(ref path) = options.verify {
+        return verify_report(path, options.trusted.as_ref());
+    }
+
+    let signer = match load_signer(options.key.as_ref()) {
+        Ok(signer) => signer,
+        Err(e) => {
+            eprintln!("Ошибка ключа подписи: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let detector = match load_detector(options.database.as_ref()) {
+        Ok(detector) => detector,
+        Err(e) => {
+            eprintln!("Ошибка базы: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    options.launch |= options.all_instances;
+    let instances = if options.launch { launchers::discover() } else { Vec::new() };
+    if options.all_instances {
+        for instance in &instances {
+            let mods = instance.mods_dir();
+            if mods.is_dir() {
+                if options.format == Format::Text {
+                    println!("Инстанс: {} - {}", instance.title(), mods.display());
+                }
+                options.paths.push(mods);
+            }
+        }
+        // Проверка без единой папки ничего не проверила - это не "чисто"
+        if options.paths.is_empty() {
+            eprintln!("Ошибка: не найдено ни одного инстанса с папкой mods, укажите путь явно");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    }
+
+    let mut report = ScanReport::new(&detector, &options.paths, SystemTime::now());
+    let started = Instant::now();
+    let mut scanner = Scanner::new(detector);
+    if options.launch {
+        scanner = scanner.with_launch_analysis(instances);
+    }
+    let (sender, receiver) = mpsc::channel();
+    let paths = options.paths.clone();
+    let threads = options.threads;
+    thread::spawn(move || scanner.scan_paths(&paths, sender, threads));
+
+    for msg in receiver {
+        match (&msg, options.format) {
+            (ScanMessage::ThreatFound(threat), Format::Text) => print_threat(threat),
+            (ScanMessage::ThreatFound(threat), Format::JsonLines) => {
+                println!("{}", serde_json::to_string(&Event::Threat(threat)).unwrap_or_default());
+            }
+            (ScanMessage::Error(error), Format::Text) => {
+                eprintln!("[{}] {} - {}", error.kind.label(), error.path, error.message);
+            }
+            (ScanMessage::Error(error), Format::JsonLines) => {
+                println!("{}", serde_json::to_string(&Event::Error(error)).unwrap_or_default());
+            }
+            (ScanMessage::JarChecked(jar), Format::JsonLines) => {
+                println!("{}", serde_json::to_string(&Event::Jar(jar)).unwrap_or_default());
+            }
+            (ScanMessage::ExtraPath(path), Format::Text) => println!("Также проверено: {}", path.display()),
+            (ScanMessage::Complete, _) => break,
+            _ => {}
+        }
+        report.record(&msg);
+    }
+    report.finish(started.elapsed());
+    report.challenge = options.challenge.clone();
+    if let Some(ref signer) = signer {
+        if let Err(e) = signer.sign(&mut report) {
+            eprintln!("Ошибка подписи: {}", e);
+        }
+    }
+
+    let stats = &report.stats;
+    let mut code = exit_code(report.verdict());
+    match options.format {
+        Format::Text => {
+            println!(
+                "Проверено: {}/{} | Угроз: {} | Подозрительных: {} | Подавлено: {} | Ошибок: {}",
+                stats.checked, stats.total, stats.found, stats.suspicious, stats.suppressed, report.errors.len(),
+            );
+            println!("Итог: {}", report.verdict().label());
+            if let Some(code) = report.verification_code() {
+                println!("Код проверки: {}", code);
+            }
+        }
+        Format::JsonLines => {
+            let summary = Event::Summary {
+                stats,
+                exit_code: code,
+                verification_code: report.verification_code(),
+            };
+            println!("{}", serde_json::to_string(&summary).unwrap_or_default());
+        }
+        // Полный отчёт - тот же, что сохраняет --output
+        Format::Json => println!("{}", report.render(ReportFormat::Json).unwrap_or_default()),
+    }
+
+    if let Some((ref path, format)) = options.output {
+        if let Err(e) = report.save(path, format) {
+            eprintln!("Ошибка записи отчёта: {}", e);
+            code = EXIT_USAGE;
+        }
+    }
+    ExitCode::from(code)
+}
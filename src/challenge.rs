@@ -0,0 +1,112 @@
+// challenge.rs - Короткий код для живой проверки.
+// Модератор называет фразу, игрок сканирует и читает код вслух; модератор пересчитывает
+// код по присланному отчёту. Если экран "0 найдено" подрисован вручную, код не совпадёт.
+// Это хеш без секретного ключа: небрежную правку он выдаст, но тот, кто знает
+// алгоритм (он открыт), посчитает верный код и для поддельного отчёта
+use sha2::{Digest, Sha256};
+use crate::report::ScanReport;
+
+// Crockford base32: без I, L, O, U - их легко спутать на слух и на глаз
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// 8 символов по 5 бит = 40 бит
+const CODE_LEN: usize = 8;
+// v2: в код входят пути, версии сканера и базы
+const CODE_VERSION: &str = "sbcheats-code-v2";
+
+// Регистр и лишние пробелы не важны: фразу обычно диктуют голосом
+pub fn normalize_challenge(challenge: &str) -> String {
+    challenge.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Порядок путей, находок и ошибок не важен: потоки сканера отдают их в разном порядке.
+// Пути и версии в коде: отчёт по другой папке или старой базой с тем же итогом не подойдёт
+pub fn verification_code(challenge: &str, report: &ScanReport) -> String {
+    let stats = &report.stats;
+    let mut paths: Vec<&str> = report.paths.iter().map(String::as_str).collect();
+    paths.sort();
+    let mut findings: Vec<String> = report.threats.iter()
+        .map(|t| format!("{}|{:?}|{}|{}", t.hashes.sha256, t.kind, t.cheat_type, t.suppressed.is_some()))
+        .collect();
+    findings.sort();
+    let mut failed: Vec<&str> = report.errors.iter().map(|e| e.path.as_str()).collect();
+    failed.sort();
+
+    let mut hasher = Sha256::new();
+    for part in [
+        CODE_VERSION.to_string(),
+        normalize_challenge(challenge),
+        report.started_at.to_string(),
+        report.scanner_version.clone(),
+        report.database_version.clone(),
+        format!("{}/{}/{}/{}/{}", stats.total, stats.checked, stats.found, stats.suspicious, stats.suppressed),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update(b"\n");
+    }
+    for line in paths.into_iter().chain(findings.iter().map(String::as_str)).chain(failed) {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    let digest = hasher.finalize();
+
+    let bits = digest[..5].iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+    let chars: String = (0..CODE_LEN)
+        .map(|i| ALPHABET[((bits >> (35 - 5 * i)) & 0x1f) as usize] as char)
+        .collect();
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+    use super::*;
+    use crate::detector::CheatDetector;
+    use crate::errors::{ScanError, ScanErrorKind};
+
+    fn report(paths: &[&str]) -> ScanReport {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        let mut report = ScanReport::new(&CheatDetector::new(), &paths, SystemTime::UNIX_EPOCH);
+        report.stats.total = 2;
+        report.stats.checked = 2;
+        report
+    }
+
+    #[test]
+    fn code_ignores_phrase_case_and_order() {
+        let mut first = report(&["mods", "versions"]);
+        first.errors = vec![
+            ScanError::new(ScanErrorKind::Io, "a.jar", "нет доступа"),
+            ScanError::new(ScanErrorKind::Io, "b.jar", "нет доступа"),
+        ];
+        let mut second = report(&["versions", "mods"]);
+        second.errors = first.errors.iter().rev().cloned().collect();
+
+        let code = verification_code("Red  Apple", &first);
+        assert_eq!(code, verification_code("red apple", &second));
+        assert_eq!(code.len(), 9);
+        assert!(code.chars().all(|c| c == '-' || ALPHABET.contains(&(c as u8))));
+    }
+
+    #[test]
+    fn code_covers_phrase_paths_versions_and_stats() {
+        let base = report(&["mods"]);
+        let code = verification_code("red apple", &base);
+        assert_ne!(code, verification_code("green apple", &base));
+
+        let mut other = report(&["other/mods"]);
+        assert_ne!(code, verification_code("red apple", &other));
+        other = report(&["mods"]);
+        other.database_version = "2024.01".to_string();
+        assert_ne!(code, verification_code("red apple", &other));
+        other = report(&["mods"]);
+        other.scanner_version = "5.0.0".to_string();
+        assert_ne!(code, verification_code("red apple", &other));
+        other = report(&["mods"]);
+        other.stats.checked = 1;
+        assert_ne!(code, verification_code("red apple", &other));
+    }
+}
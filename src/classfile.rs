@@ -0,0 +1,485 @@
+// classfile.rs - Разбор constant pool в .class файлах
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+use std::time::Instant;
+use zip::ZipArchive;
+
+// Классы больше этого размера не разбираются
+const MAX_CLASS_SIZE: u64 = 4 * 1024 * 1024;
+// Вложенность массивов и аннотаций в element_value: глубже только у специально
+// собранного класса, а рекурсия без предела роняет поток сканера
+const MAX_ANNOTATION_DEPTH: usize = 64;
+
+const TAG_UTF8: u8 = 1;
+const TAG_INTEGER: u8 = 3;
+const TAG_FLOAT: u8 = 4;
+const TAG_LONG: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_CLASS: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_FIELDREF: u8 = 9;
+const TAG_METHODREF: u8 = 10;
+const TAG_INTERFACE_METHODREF: u8 = 11;
+const TAG_NAME_AND_TYPE: u8 = 12;
+const TAG_METHOD_HANDLE: u8 = 15;
+const TAG_METHOD_TYPE: u8 = 16;
+const TAG_DYNAMIC: u8 = 17;
+const TAG_INVOKE_DYNAMIC: u8 = 18;
+const TAG_MODULE: u8 = 19;
+const TAG_PACKAGE: u8 = 20;
+
+#[derive(Debug, Clone)]
+enum Constant {
+    Utf8(String),
+    Class(u16),
+    String(u16),
+    MemberRef { tag: u8, class: u16, name_and_type: u16 },
+    NameAndType { name: u16, descriptor: u16 },
+    // Числа, MethodHandle, invokedynamic и пр. - для сигнатур не нужны
+    Other,
+    // Второй слот Long/Double
+    Unusable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemberRef {
+    pub owner: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+// Аннотация класса или метода: тип + значения элементов, сведённые к строкам
+// (строковые константы и имена классов из 'c'-значений)
+#[derive(Debug, Clone, Default)]
+pub struct Annotation {
+    // Дескриптор типа: "Lorg/spongepowered/asm/mixin/Mixin;"
+    pub type_descriptor: String,
+    pub elements: Vec<(String, Vec<String>)>,
+}
+
+impl Annotation {
+    pub fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.elements.iter()
+            .filter(move |(n, _)| n == name)
+            .flat_map(|(_, values)| values.iter().map(String::as_str))
+    }
+}
+
+// Метод с аннотациями: у Mixin это @Inject, @Redirect, @Overwrite и т.п.
+#[derive(Debug, Clone, Default)]
+pub struct Method {
+    pub name: String,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClassFile {
+    pub this_class: String,
+    // RuntimeVisible + RuntimeInvisible аннотации класса
+    pub annotations: Vec<Annotation>,
+    pub methods: Vec<Method>,
+    // Все CONSTANT_Utf8
+    pub utf8: Vec<String>,
+    // Строковые литералы (CONSTANT_String)
+    pub strings: Vec<String>,
+    // Внутренние имена классов: "net/minecraft/class_636"
+    pub class_refs: Vec<String>,
+    pub method_refs: Vec<MemberRef>,
+    pub field_refs: Vec<MemberRef>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, String> {
+        let b = *self.data.get(self.pos).ok_or("неожиданный конец файла")?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes([self.u8()?, self.u8()?, self.u8()?, self.u8()?]))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).ok_or("неверная длина")?;
+        let slice = self.data.get(self.pos..end).ok_or("неожиданный конец файла")?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+}
+
+impl ClassFile {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut r = Reader { data, pos: 0 };
+        if r.u32()? != 0xCAFE_BABE {
+            return Err("нет сигнатуры CAFEBABE".to_string());
+        }
+        r.skip(4)?; // minor + major version
+
+        let pool = read_constant_pool(&mut r)?;
+
+        let utf8_at = |index: u16| -> Option<&str> {
+            match pool.get(index as usize)? {
+                Constant::Utf8(s) => Some(s.as_str()),
+                _ => None,
+            }
+        };
+        let class_at = |index: u16| -> Option<&str> {
+            match pool.get(index as usize)? {
+                Constant::Class(name) => utf8_at(*name),
+                _ => None,
+            }
+        };
+
+        let mut class = ClassFile::default();
+        for constant in &pool {
+            match constant {
+                Constant::Utf8(s) => class.utf8.push(s.clone()),
+                Constant::String(index) => {
+                    if let Some(s) = utf8_at(*index) {
+                        class.strings.push(s.to_string());
+                    }
+                }
+                Constant::Class(index) => {
+                    if let Some(s) = utf8_at(*index) {
+                        class.class_refs.push(s.to_string());
+                    }
+                }
+                Constant::MemberRef { tag, class: owner, name_and_type } => {
+                    let Some(Constant::NameAndType { name, descriptor }) = pool.get(*name_and_type as usize) else {
+                        continue;
+                    };
+                    let (Some(owner), Some(name), Some(descriptor)) =
+                        (class_at(*owner), utf8_at(*name), utf8_at(*descriptor)) else {
+                        continue;
+                    };
+                    let member = MemberRef {
+                        owner: owner.to_string(),
+                        name: name.to_string(),
+                        descriptor: descriptor.to_string(),
+                    };
+                    if *tag == TAG_FIELDREF {
+                        class.field_refs.push(member);
+                    } else {
+                        class.method_refs.push(member);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        r.skip(2)?; // access_flags
+        class.this_class = class_at(r.u16()?).unwrap_or_default().to_string();
+        r.skip(2)?; // super_class
+        let interfaces_count = r.u16()? as usize;
+        r.skip(interfaces_count * 2)?;
+
+        // Поля пропускаем целиком, у методов нужны только имя и аннотации
+        let fields = r.u16()?;
+        for _ in 0..fields {
+            r.skip(6)?; // access_flags, name, descriptor
+            read_annotations(&mut r, &utf8_at)?;
+        }
+        let methods = r.u16()?;
+        for _ in 0..methods {
+            r.skip(2)?; // access_flags
+            let name = utf8_at(r.u16()?).unwrap_or_default().to_string();
+            r.skip(2)?; // descriptor
+            let annotations = read_annotations(&mut r, &utf8_at)?;
+            class.methods.push(Method { name, annotations });
+        }
+
+        class.annotations = read_annotations(&mut r, &utf8_at)?;
+        Ok(class)
+    }
+}
+
+// Таблица атрибутов: аннотации разбираются, остальное пропускается
+fn read_annotations<'p>(
+    r: &mut Reader,
+    utf8_at: &impl Fn(u16) -> Option<&'p str>,
+) -> Result<Vec<Annotation>, String> {
+    let mut annotations = Vec::new();
+    let attributes = r.u16()?;
+    for _ in 0..attributes {
+        let name = utf8_at(r.u16()?).unwrap_or_default();
+        let len = r.u32()? as usize;
+        let body = r.bytes(len)?;
+        if name == "RuntimeVisibleAnnotations" || name == "RuntimeInvisibleAnnotations" {
+            let mut ar = Reader { data: body, pos: 0 };
+            let count = ar.u16()?;
+            for _ in 0..count {
+                annotations.push(read_annotation(&mut ar, &utf8_at, 0)?);
+            }
+        }
+    }
+    Ok(annotations)
+}
+
+fn read_annotation<'p>(
+    r: &mut Reader,
+    utf8_at: &impl Fn(u16) -> Option<&'p str>,
+    depth: usize,
+) -> Result<Annotation, String> {
+    let type_descriptor = utf8_at(r.u16()?).unwrap_or_default().to_string();
+    let pairs = r.u16()?;
+    let mut elements = Vec::with_capacity(pairs as usize);
+    for _ in 0..pairs {
+        let name = utf8_at(r.u16()?).unwrap_or_default().to_string();
+        let mut values = Vec::new();
+        read_element_value(r, utf8_at, &mut values, depth)?;
+        elements.push((name, values));
+    }
+    Ok(Annotation { type_descriptor, elements })
+}
+
+fn read_element_value<'p>(
+    r: &mut Reader,
+    utf8_at: &impl Fn(u16) -> Option<&'p str>,
+    out: &mut Vec<String>,
+    depth: usize,
+) -> Result<(), String> {
+    if depth >= MAX_ANNOTATION_DEPTH {
+        return Err(format!("вложенность аннотации глубже {}", MAX_ANNOTATION_DEPTH));
+    }
+    match r.u8()? {
+        b's' => {
+            if let Some(s) = utf8_at(r.u16()?) {
+                out.push(s.to_string());
+            }
+        }
+        // "Lnet/minecraft/class_636;" -> "net/minecraft/class_636"
+        b'c' => {
+            if let Some(s) = utf8_at(r.u16()?) {
+                let name = s.strip_prefix('L').and_then(|n| n.strip_suffix(';')).unwrap_or(s);
+                out.push(name.to_string());
+            }
+        }
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => r.skip(2)?,
+        b'e' => r.skip(4)?,
+        b'@' => {
+            read_annotation(r, utf8_at, depth + 1)?;
+        }
+        b'[' => {
+            let count = r.u16()?;
+            for _ in 0..count {
+                read_element_value(r, utf8_at, out, depth + 1)?;
+            }
+        }
+        other => return Err(format!("неизвестный тег element_value: {}", other)),
+    }
+    Ok(())
+}
+
+// Индекс 0 не используется, Long/Double занимают два слота
+fn read_constant_pool(r: &mut Reader) -> Result<Vec<Constant>, String> {
+    let count = r.u16()? as usize;
+    let mut pool = Vec::with_capacity(count);
+    pool.push(Constant::Unusable);
+
+    while pool.len() < count {
+        let tag = r.u8()?;
+        let constant = match tag {
+            TAG_UTF8 => {
+                let len = r.u16()? as usize;
+                Constant::Utf8(decode_modified_utf8(r.bytes(len)?))
+            }
+            TAG_CLASS => Constant::Class(r.u16()?),
+            TAG_STRING => Constant::String(r.u16()?),
+            TAG_FIELDREF | TAG_METHODREF | TAG_INTERFACE_METHODREF => Constant::MemberRef {
+                tag,
+                class: r.u16()?,
+                name_and_type: r.u16()?,
+            },
+            TAG_NAME_AND_TYPE => Constant::NameAndType {
+                name: r.u16()?,
+                descriptor: r.u16()?,
+            },
+            TAG_INTEGER | TAG_FLOAT => {
+                r.skip(4)?;
+                Constant::Other
+            }
+            TAG_LONG | TAG_DOUBLE => {
+                r.skip(8)?;
+                pool.push(Constant::Other);
+                Constant::Unusable
+            }
+            TAG_METHOD_HANDLE => {
+                r.skip(3)?;
+                Constant::Other
+            }
+            TAG_METHOD_TYPE | TAG_MODULE | TAG_PACKAGE => {
+                r.skip(2)?;
+                Constant::Other
+            }
+            TAG_DYNAMIC | TAG_INVOKE_DYNAMIC => {
+                r.skip(4)?;
+                Constant::Other
+            }
+            other => return Err(format!("неизвестный тег constant pool: {}", other)),
+        };
+        pool.push(constant);
+    }
+
+    Ok(pool)
+}
+
+// "Modified UTF-8" JVM: 0 кодируется как C0 80, символы вне BMP - суррогатными парами
+fn decode_modified_utf8(bytes: &[u8]) -> String {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b & 0x80 == 0 {
+            units.push(b as u16);
+            i += 1;
+        } else if b & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+            units.push(((b as u16 & 0x1F) << 6) | (bytes[i + 1] as u16 & 0x3F));
+            i += 2;
+        } else if b & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+            units.push(
+                ((b as u16 & 0x0F) << 12)
+                    | ((bytes[i + 1] as u16 & 0x3F) << 6)
+                    | (bytes[i + 2] as u16 & 0x3F),
+            );
+            i += 3;
+        } else {
+            units.push(0xFFFD);
+            i += 1;
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+// Сводный индекс по всем классам jar: значение -> первый класс, где оно встретилось
+#[derive(Debug, Clone, Default)]
+pub struct ConstantIndex {
+    pub utf8: HashMap<String, String>,
+    pub strings: HashMap<String, String>,
+    pub class_refs: HashMap<String, String>,
+    // "owner.name"
+    pub method_refs: HashMap<String, String>,
+    pub field_refs: HashMap<String, String>,
+}
+
+impl ConstantIndex {
+    // После deadline классы больше не разбираются - вызывающий отметит нехватку времени
+    pub fn build<R: Read + Seek>(archive: &mut ZipArchive<R>, deadline: Instant) -> Self {
+        let mut index = Self::default();
+        let mut buf = Vec::new();
+
+        for i in 0..archive.len() {
+            if Instant::now() > deadline {
+                break;
+            }
+            let Ok(file) = archive.by_index(i) else { continue };
+            if !file.name().ends_with(".class") || file.size() > MAX_CLASS_SIZE {
+                continue;
+            }
+            let entry = file.name().trim_end_matches(".class").to_string();
+
+            buf.clear();
+            if file.take(MAX_CLASS_SIZE).read_to_end(&mut buf).is_err() {
+                continue;
+            }
+            let Ok(class) = ClassFile::parse(&buf) else { continue };
+
+            index.add(&entry, class);
+        }
+
+        index
+    }
+
+    fn add(&mut self, entry: &str, class: ClassFile) {
+        let owner = if class.this_class.is_empty() { entry } else { class.this_class.as_str() };
+        let insert = |map: &mut HashMap<String, String>, key: String| {
+            map.entry(key).or_insert_with(|| owner.to_string());
+        };
+
+        for s in class.utf8 {
+            insert(&mut self.utf8, s);
+        }
+        for s in class.strings {
+            insert(&mut self.strings, s);
+        }
+        for c in class.class_refs {
+            insert(&mut self.class_refs, c);
+        }
+        for m in class.method_refs {
+            insert(&mut self.method_refs, format!("{}.{}", m.owner, m.name));
+        }
+        for f in class.field_refs {
+            insert(&mut self.field_refs, format!("{}.{}", f.owner, f.name));
+        }
+    }
+}
+
+// "net.minecraft.Foo" и "net/minecraft/Foo" - одно и то же
+pub fn internal_name(name: &str) -> String {
+    name.replace('.', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{ClassBuilder, Element};
+
+    const MIXIN: &str = "Lorg/spongepowered/asm/mixin/Mixin;";
+
+    #[test]
+    fn reads_class_annotation_values() {
+        let mut builder = ClassBuilder::new("cheat/mixin/ReachMixin");
+        let annotation = builder.annotation(MIXIN, &[
+            ("value", Element::Array(vec![Element::Class("net/minecraft/class_636")])),
+            ("targets", Element::Str("net.minecraft.client.Foo")),
+        ]);
+        let class = ClassFile::parse(&builder.class_annotation(annotation).build()).unwrap();
+
+        assert_eq!(class.this_class, "cheat/mixin/ReachMixin");
+        assert_eq!(class.annotations.len(), 1);
+        let values: Vec<&str> = class.annotations[0].values("value").collect();
+        assert_eq!(values, ["net/minecraft/class_636"]);
+        assert_eq!(class.annotations[0].values("targets").collect::<Vec<_>>(), ["net.minecraft.client.Foo"]);
+    }
+
+    #[test]
+    fn rejects_deeply_nested_annotation() {
+        // 100 000 вложенных массивов по 3 байта - без предела это переполнение стека
+        let mut builder = ClassBuilder::new("Bomb");
+        let mut annotation = builder.annotation(MIXIN, &[]);
+        annotation[2..4].copy_from_slice(&1u16.to_be_bytes());
+        annotation.extend(builder.utf8("value").to_be_bytes());
+        for _ in 0..100_000 {
+            annotation.extend([b'[', 0, 1]);
+        }
+        annotation.push(b'Z');
+        annotation.extend(builder.utf8("x").to_be_bytes());
+
+        let error = ClassFile::parse(&builder.class_annotation(annotation).build()).unwrap_err();
+        assert!(error.contains("вложенность"), "{}", error);
+    }
+
+    #[test]
+    fn allows_nesting_below_limit() {
+        let mut builder = ClassBuilder::new("Nested");
+        let mut value = Element::Str("deep");
+        for _ in 0..MAX_ANNOTATION_DEPTH - 1 {
+            value = Element::Array(vec![value]);
+        }
+        let annotation = builder.annotation(MIXIN, &[("value", value)]);
+        let class = ClassFile::parse(&builder.class_annotation(annotation).build()).unwrap();
+        assert_eq!(class.annotations[0].values("value").collect::<Vec<_>>(), ["deep"]);
+    }
+}
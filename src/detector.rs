@@ -1,187 +1,1047 @@
 // detector.rs - Исправленная логика детектора
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
-use std::path::Path;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use zip::ZipArchive;
 use serde::{Deserialize, Serialize};
+use crate::allowlist::Allowlist;
+use crate::classfile::ConstantIndex;
+use crate::errors::{ScanError, ScanErrorKind};
+use crate::hashes::FileHashes;
+use crate::injection::{InjectionKind, LaunchReference};
+use crate::integrity::{self, ExpectedHash, LibraryFile, Modification};
+use crate::limits::{ScanLimits, RATIO_MIN_SIZE};
+use crate::logs::{self, LogEvidence};
+use crate::metadata::{JarMetadata, ModMetadata};
+use crate::mixin::{self, MixinReport};
+use crate::obfuscation::{self, ObfuscationReport};
+use crate::rules::Rule;
+use crate::sniff;
+use crate::versions::VersionInfo;
+use crate::zipcheck;
+
+// Записи меньше этого размера не проверяются на ZIP-сигнатуру
+const MIN_SNIFF_SIZE: u64 = 1024;
+// Баллы за архив, расширение которого не соответствует содержимому
+const DISGUISE_SCORE: u32 = 60;
+// Баллы за ручную правку структуры ZIP
+const TAMPERING_SCORE: u32 = 70;
+// Баллы за архив, превысивший лимиты разбора
+const ABUSE_SCORE: u32 = 80;
+// Баллы за jar, который подгружается при запуске в обход mods/
+const LAUNCH_SCORE: u32 = 70;
+// Баллы за версию с чужим mainClass или неизвестными библиотеками
+const VERSION_SCORE: u32 = 60;
+// Уверенность совпадения по одному имени jar в логе
+const LOG_JAR_NAME_CONFIDENCE: u32 = 50;
+// Имена сигнатур короче этого по имени jar не ищутся: слишком много случайных совпадений
+const MIN_JAR_NAME_KEY: usize = 4;
+
+// Имена файлов базы, которые ищутся рядом с exe
+const DATABASE_FILE_NAMES: [&str; 2] = ["signatures.toml", "signatures.json"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheatInfo {
+    #[serde(default)]
     pub directories: Vec<String>,
+    #[serde(default)]
     pub classes: Vec<String>,
+    #[serde(default)]
     pub exclude_dirs: Vec<String>,
+    #[serde(default)]
     pub sizes_kb: Vec<f32>,
     pub description: String,
+    #[serde(default)]
     pub strict_mode: bool,
+    #[serde(default = "default_min_conditions")]
     pub min_conditions: usize,
+    // id мода из fabric.mod.json / quilt.mod.json / mods.toml - альтернатива остальным критериям
+    #[serde(default)]
+    pub mod_ids: Vec<String>,
+    // Известные хеши сборок (SHA-256 / SHA-1 / murmur2) - совпадение считается точным
+    #[serde(default)]
+    pub hashes: Vec<String>,
+    // Явное условие. Если не задано - строится из полей выше (см. legacy_rule)
+    #[serde(default)]
+    pub rule: Option<Rule>,
+}
+
+fn default_min_conditions() -> usize {
+    2
+}
+
+impl CheatInfo {
+    // Старая логика в виде выражения:
+    // директория обязательна, остальные критерии добираются до min_conditions
+    pub fn legacy_rule(&self) -> Rule {
+        let core = self.legacy_core_rule();
+        if self.mod_ids.is_empty() {
+            return core;
+        }
+        let mut alternatives: Vec<Rule> = self.mod_ids.iter().cloned().map(Rule::ModId).collect();
+        alternatives.push(core);
+        Rule::Any(alternatives)
+    }
+
+    fn legacy_core_rule(&self) -> Rule {
+        let any_of = |items: &[String], leaf: fn(String) -> Rule| {
+            Rule::Any(items.iter().cloned().map(leaf).collect())
+        };
+
+        let mut required = Vec::new();
+        let mut optional = Vec::new();
+
+        if !self.directories.is_empty() {
+            required.push(any_of(&self.directories, Rule::Directory));
+        }
+
+        if self.strict_mode {
+            if !self.classes.is_empty() {
+                required.push(any_of(&self.classes, Rule::Class));
+            }
+        } else {
+            if !self.classes.is_empty() {
+                optional.push(any_of(&self.classes, Rule::Class));
+            }
+            if !self.sizes_kb.is_empty() {
+                optional.push(Rule::Size { kb: self.sizes_kb.clone(), tolerance: 0.05 });
+            }
+        }
+
+        let need = self.min_conditions
+            .saturating_sub(required.len())
+            .min(optional.len());
+        if need > 0 {
+            required.push(Rule::AtLeast { n: need, of: optional });
+        }
+
+        if required.is_empty() {
+            // Нечего проверять - сигнатура никогда не срабатывает
+            return Rule::Any(Vec::new());
+        }
+
+        if self.strict_mode && !self.exclude_dirs.is_empty() {
+            required.push(Rule::Not(Box::new(any_of(&self.exclude_dirs, Rule::Directory))));
+        }
+
+        Rule::All(required)
+    }
+}
+
+// Всё, что известно о jar на момент проверки сигнатур
+#[derive(Default)]
+pub struct JarContext {
+    // Полная цепочка: "C:/mods/wrapper.jar!/META-INF/jars/inner.jar"
+    pub path: String,
+    // Имя самого внутреннего архива
+    pub name: String,
+    // 0 - файл на диске, 1 - jar внутри него и т.д.
+    pub depth: usize,
+    pub file_size: u64,
+    // Имена записей архива, см. normalize_entry
+    pub entries: Vec<String>,
+    pub metadata: JarMetadata,
+    pub hashes: FileHashes,
+    // Пустой, если ни одна сигнатура не смотрит в constant pool
+    pub constants: ConstantIndex,
+    // Архив под чужим расширением (.png, .dat, без расширения)
+    pub disguise: Option<String>,
+    // Признаки ручной правки ZIP, см. zipcheck
+    pub tampering: Vec<String>,
+    // Нарушенные лимиты разбора, см. ScanLimits
+    pub abuse: Vec<String>,
+    // Загружается при запуске игры: -javaagent, --tweakClass (см. injection)
+    pub launch: Option<String>,
+    // Нестандартная версия в versions/, для которой это jar (см. versions)
+    pub version: Vec<String>,
+    // Хеш не совпал с json версии (см. integrity)
+    pub modified: Option<Modification>,
+}
+
+// Откуда взялся архив: файл на диске или запись внутри другого архива
+struct ArchiveOrigin {
+    path: String,
+    name: String,
+    depth: usize,
+    disguise: Option<String>,
+    launch: Option<String>,
+    version: Vec<String>,
+    expected: Option<ExpectedHash>,
+}
+
+impl JarContext {
+    pub fn size_kb(&self) -> f32 {
+        self.file_size as f32 / 1024.0
+    }
+}
+
+// Формат внешнего файла базы (JSON или TOML)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignatureDatabase {
+    #[serde(default)]
+    pub version: String,
+    // false: сигнатуры из файла дополняют/переопределяют встроенные
+    // true: используется только файл
+    #[serde(default)]
+    pub replace_builtin: bool,
+    #[serde(default)]
+    pub signatures: BTreeMap<String, CheatInfo>,
+    // Лимиты разбора архивов; без секции - значения по умолчанию
+    #[serde(default)]
+    pub limits: Option<ScanLimits>,
+}
+
+impl SignatureDatabase {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        let is_toml = path.extension()
+            .and_then(|s| s.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+
+        if is_toml {
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+    }
+}
+
+// Одна сработавшая сигнатура
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureMatch {
+    pub cheat_type: String,
+    pub description: String,
+    pub match_score: usize,
+    pub max_score: usize,
+    // 0..=100
+    pub confidence: u32,
+    // Совпал известный хеш сборки - вердикт точный
+    pub exact_hash: Option<String>,
+    pub hits: Vec<String>,
+}
+
+// Откуда взялась находка
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    // Совпадение с сигнатурой из базы
+    Signature,
+    // Инъекции Mixin в чувствительные классы Minecraft
+    MixinHeuristic,
+    // Архив под чужим расширением (.png, .dat, без расширения)
+    DisguisedArchive,
+    // Сломанный или подделанный central directory, дубликаты, пересечения
+    TamperedArchive,
+    // Архив превысил лимиты разбора: zip-бомба или намеренная перегрузка
+    AbusiveArchive,
+    // Признаки обфускации у jar без известной сигнатуры
+    Obfuscation,
+    // Jar подгружается при запуске: -javaagent, -agentpath, --tweakClass
+    LaunchInjection,
+    // Версия в versions/ с чужим mainClass или неизвестными библиотеками
+    CustomVersion,
+    // Jar клиента или библиотеки не совпадает с SHA-1 из json версии
+    ModifiedJar,
+    // Мод, jar или класс из базы упомянут в логе или краш-репорте
+    LogEvidence,
+}
+
+impl FindingKind {
+    // Эвристика - повод проверить руками, а не угроза
+    pub fn is_heuristic(self) -> bool {
+        matches!(self, FindingKind::MixinHeuristic
+            | FindingKind::DisguisedArchive
+            | FindingKind::TamperedArchive
+            | FindingKind::AbusiveArchive
+            | FindingKind::Obfuscation
+            | FindingKind::LaunchInjection
+            | FindingKind::CustomVersion
+            | FindingKind::ModifiedJar)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FindingKind::Signature => "Сигнатура",
+            FindingKind::MixinHeuristic => "Эвристика Mixin",
+            FindingKind::DisguisedArchive => "Замаскированный архив",
+            FindingKind::TamperedArchive => "Подделанная структура ZIP",
+            FindingKind::AbusiveArchive => "Превышены лимиты разбора",
+            FindingKind::Obfuscation => "Эвристика обфускации",
+            FindingKind::LaunchInjection => "Внедрение при запуске",
+            FindingKind::CustomVersion => "Нестандартная версия",
+            FindingKind::ModifiedJar => "Изменённый jar игры",
+            FindingKind::LogEvidence => "След в логах",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThreatResult {
+    pub kind: FindingKind,
     pub path: String,
     pub name: String,
     pub size: u64,
+    // Основной вердикт = matches[0] (для сигнатур)
     pub cheat_type: String,
     pub details: Vec<String>,
     pub match_score: usize,
+    pub confidence: u32,
+    pub exact_hash: Option<String>,
+    pub hashes: FileHashes,
+    // Сборка в allowlist: причина подавления. Такие результаты не считаются угрозой
+    pub suppressed: Option<String>,
+    pub metadata: JarMetadata,
+    // Все сработавшие сигнатуры: по убыванию уверенности, затем по имени
+    pub matches: Vec<SignatureMatch>,
+}
+
+impl ThreatResult {
+    // Внедрение при запуске без сигнатуры - подозрение, с сигнатурой - угроза
+    pub fn is_heuristic(&self) -> bool {
+        self.kind.is_heuristic() && self.matches.is_empty()
+    }
+
+    // Находка без сигнатуры: эвристики, проблемы структуры архива и т.п.
+    fn from_context(
+        ctx: JarContext,
+        kind: FindingKind,
+        cheat_type: &str,
+        confidence: u32,
+        details: Vec<String>,
+        suppressed: Option<String>,
+    ) -> Self {
+        Self {
+            kind,
+            path: ctx.path,
+            name: ctx.name,
+            size: ctx.file_size,
+            cheat_type: cheat_type.to_string(),
+            details,
+            match_score: 0,
+            confidence,
+            exact_hash: None,
+            hashes: ctx.hashes,
+            suppressed,
+            metadata: ctx.metadata,
+            matches: Vec::new(),
+        }
+    }
+}
+
+// Jar с метаданными загрузчика, и чистый тоже: модератор видит, какие моды стоят
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JarRecord {
+    // Как в ThreatResult: "mods/wrapper.jar!/META-INF/jars/inner.jar"
+    pub path: String,
+    pub name: String,
+    pub sha256: String,
+    pub mods: Vec<ModMetadata>,
+}
+
+// Итог проверки одного файла на диске вместе с вложенными архивами
+#[derive(Debug, Default)]
+pub struct FileScan {
+    pub threats: Vec<ThreatResult>,
+    // Непустой - файл проверен не полностью
+    pub errors: Vec<ScanError>,
+    pub jars: Vec<JarRecord>,
 }
 
 #[derive(Clone)]
 pub struct CheatDetector {
-    database: HashMap<String, CheatInfo>,
+    // BTreeMap - порядок обхода не меняется от запуска к запуску
+    database: BTreeMap<String, CheatInfo>,
+    database_version: String,
+    database_source: Option<PathBuf>,
+    allowlist: Allowlist,
+    limits: ScanLimits,
+    // Разбирать .class только если это нужно какой-то сигнатуре
+    needs_constants: bool,
+}
+
+impl Default for CheatDetector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CheatDetector {
+    // Только встроенная база
     pub fn new() -> Self {
-        let mut database = HashMap::new();
+        let mut database = BTreeMap::new();
         Self::init_database(&mut database);
-        Self { database }
+        let mut detector = Self {
+            database,
+            database_version: format!("builtin-{}", env!("CARGO_PKG_VERSION")),
+            database_source: None,
+            allowlist: Allowlist::shipped(),
+            limits: ScanLimits::default(),
+            needs_constants: false,
+        };
+        detector.compile_rules();
+        detector
     }
 
-    pub fn check_jar_file(&self, jar_path: &Path) -> Option<ThreatResult> {
-        let file_size = std::fs::metadata(jar_path).ok()?.len();
-        let file_size_kb = file_size as f32 / 1024.0;
+    // У каждой сигнатуры после загрузки есть rule
+    fn compile_rules(&mut self) {
+        for cheat_info in self.database.values_mut() {
+            if cheat_info.rule.is_none() {
+                cheat_info.rule = Some(cheat_info.legacy_rule());
+            }
+        }
+        self.needs_constants = self.database.values()
+            .filter_map(|c| c.rule.as_ref())
+            .any(Rule::uses_constants);
+    }
 
-        let file = File::open(jar_path).ok()?;
-        let reader = BufReader::new(file);
-        let mut archive = ZipArchive::new(reader).ok()?;
+    // База из файла поверх встроенной (или вместо неё при replace_builtin)
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let file_db = SignatureDatabase::from_file(path)?;
 
-        // Собираем все имена файлов в архиве
-        let mut file_list = Vec::with_capacity(archive.len() as usize);
-        for i in 0..archive.len() {
-            if let Ok(file) = archive.by_index(i) {
-                file_list.push(file.name().to_lowercase());
+        let mut detector = Self::new();
+        if file_db.replace_builtin {
+            detector.database.clear();
+        }
+        detector.database.extend(file_db.signatures);
+        if !file_db.version.is_empty() {
+            detector.database_version = file_db.version;
+        }
+        detector.database_source = Some(path.to_path_buf());
+        detector.compile_rules();
+
+        Ok(match file_db.limits {
+            Some(limits) => detector.with_limits(limits),
+            None => detector,
+        })
+    }
+
+    // Путь к базе по умолчанию: signatures.toml / signatures.json рядом с exe
+    pub fn default_database_path() -> PathBuf {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+
+        DATABASE_FILE_NAMES.iter()
+            .map(|name| exe_dir.join(name))
+            .find(|p| p.is_file())
+            .unwrap_or_else(|| exe_dir.join(DATABASE_FILE_NAMES[1]))
+    }
+
+    pub fn with_allowlist(mut self, allowlist: Allowlist) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: ScanLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn allowlist(&self) -> &Allowlist {
+        &self.allowlist
+    }
+
+    pub fn signature_count(&self) -> usize {
+        self.database.len()
+    }
+
+    pub fn database_version(&self) -> &str {
+        &self.database_version
+    }
+
+    // None - используется встроенная база
+    pub fn database_source(&self) -> Option<&Path> {
+        self.database_source.as_deref()
+    }
+
+    // Результаты для самого файла и для всех вложенных jar
+    pub fn check_jar_file(&self, jar_path: &Path) -> FileScan {
+        self.check_file(jar_path, None, Vec::new(), None)
+    }
+
+    // Агент или твикер из аргументов запуска: находка всегда вида LaunchInjection
+    pub fn check_launch(&self, reference: &LaunchReference) -> FileScan {
+        let Some(ref path) = reference.resolved else {
+            // Агент мог быть удалён перед проверкой, а аргумент запуска остался - это уже находка.
+            // Ошибка чтения рядом с ней отмечает, что содержимое не проверено
+            let mut scan = FileScan::default();
+            let ctx = JarContext {
+                path: reference.value.clone(),
+                name: reference.value.rsplit(['/', '\\']).next().unwrap_or_default().to_string(),
+                launch: Some(reference.describe()),
+                ..JarContext::default()
+            };
+            let details = vec![
+                reference.describe(),
+                "Файл не найден, содержимое не проверено".to_string(),
+                format!("Эвристический score: {}", LAUNCH_SCORE),
+            ];
+            scan.threats.push(ThreatResult::from_context(
+                ctx,
+                FindingKind::LaunchInjection,
+                "Внедрение при запуске",
+                LAUNCH_SCORE,
+                details,
+                None,
+            ));
+            scan.errors.push(ScanError::new(
+                ScanErrorKind::Io,
+                reference.value.clone(),
+                format!("Файл не найден: {}", reference.describe()),
+            ));
+            return scan;
+        };
+        match reference.kind {
+            InjectionKind::NativeAgent => self.check_plain_file(path, Some(reference.describe()), Vec::new()),
+            InjectionKind::JavaAgent | InjectionKind::TweakClass => {
+                self.check_file(path, Some(reference.describe()), Vec::new(), None)
             }
         }
+    }
 
-        // ОТЛАДКА для DoomsDay
-        let jar_name = jar_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-        let has_net_java = file_list.iter().any(|f| f.contains("net/java/"));
-        let has_i_class = file_list.iter().any(|f| f.contains("i.class"));
+    // Jar версии проверяется всегда; без jar (inheritsFrom) находкой становится сам json
+    pub fn check_version(&self, version: &VersionInfo) -> FileScan {
+        let reasons = if version.is_suspicious() { version.reasons() } else { Vec::new() };
+        match version.jar_path {
+            Some(ref jar) => self.check_file(jar, None, reasons, version.client_hash.clone()),
+            None if !reasons.is_empty() => self.check_plain_file(&version.json_path, None, reasons),
+            None => FileScan::default(),
+        }
+    }
 
-        if has_net_java || has_i_class {
+    // Библиотека из json версии: сигнатуры плюс сверка SHA-1
+    pub fn check_library(&self, library: &LibraryFile) -> FileScan {
+        self.check_file(&library.path, None, Vec::new(), Some(library.expected.clone()))
+    }
+
+    // Лог или краш-репорт: каждая строка с модами, jar или классами проверяется
+    // по базе отдельно. Одна находка на сигнатуру в файле - по первой строке
+    pub fn check_log(&self, path: &Path) -> FileScan {
+        let mut scan = FileScan::default();
+        let log = match logs::read(path, self.limits.max_uncompressed_size) {
+            Ok(log) => log,
+            Err(e) => {
+                scan.errors.push(ScanError::new(ScanErrorKind::Io, path.display().to_string(), e));
+                return scan;
+            }
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
 
-            // Проверяем exclude_dirs
-            let excludes = vec![
-                "org/apache/", "com/google/", "io/netty/",
-                "net/minecraft/", "net/minecraftforge/", "optifine/", "javax/"
+        let mut reported: Vec<String> = Vec::new();
+        for evidence in &log.evidence {
+            // У строки лога нет файла: хеши пустые, и правила по хешу к ней не применяются
+            let ctx = JarContext {
+                path: format!("{}:{}", path.display(), evidence.line),
+                name: name.clone(),
+                entries: evidence.classes.clone(),
+                metadata: JarMetadata { mods: evidence.mods.clone(), ..JarMetadata::default() },
+                ..JarContext::default()
+            };
+            let mut matches = self.match_signatures(&ctx);
+            matches.extend(self.match_jar_names(evidence)
+                .filter(|m| !matches.iter().any(|other| other.cheat_type == m.cheat_type))
+                .collect::<Vec<_>>());
+            matches.retain(|m| !reported.contains(&m.cheat_type));
+            if matches.is_empty() {
+                continue;
+            }
+            reported.extend(matches.iter().map(|m| m.cheat_type.clone()));
+
+            let primary = matches[0].clone();
+            let mut details = vec![
+                primary.description.clone(),
+                format!("Загружался {}", evidence.when(log.date.as_deref())),
+                format!("Строка {}: {}", evidence.line, evidence.shown_text()),
             ];
-            for ex in &excludes {
-                if file_list.iter().any(|f| f.contains(ex)) {
+            details.extend(primary.hits.iter().cloned());
+            let mut threat = ThreatResult::from_context(
+                ctx,
+                FindingKind::LogEvidence,
+                &primary.cheat_type,
+                primary.confidence,
+                details,
+                // Allowlist описывает конкретные сборки по хешу и версии, а строка
+                // лога не доказывает, какой именно файл загружался
+                None,
+            );
+            threat.match_score = primary.match_score;
+            threat.matches = matches;
+            scan.threats.push(threat);
+        }
+        scan
+    }
 
+    // "Freecam-1.2.jar" -> Freecam: имя сигнатуры в начале имени jar без разделителей
+    fn match_jar_names<'a>(&'a self, evidence: &'a LogEvidence) -> impl Iterator<Item = SignatureMatch> + 'a {
+        self.database.iter()
+            .filter_map(move |(cheat_name, cheat_info)| {
+                let key = compact_name(cheat_name);
+                if key.len() < MIN_JAR_NAME_KEY {
+                    return None;
                 }
+                let jar = evidence.jars.iter().find(|jar| compact_name(jar).starts_with(&key))?;
+                Some(SignatureMatch {
+                    cheat_type: cheat_name.clone(),
+                    description: cheat_info.description.clone(),
+                    match_score: 1,
+                    max_score: 1,
+                    confidence: LOG_JAR_NAME_CONFIDENCE,
+                    exact_hash: None,
+                    hits: vec![format!("Jar: {}", jar)],
+                })
+            })
+    }
+
+    // Не архив (нативный агент, json версии): только хеши и allowlist
+    fn check_plain_file(&self, path: &Path, launch: Option<String>, version: Vec<String>) -> FileScan {
+        let mut scan = FileScan::default();
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) => {
+                scan.errors.push(ScanError::from_io(path, &e));
+                return scan;
             }
+        };
+        let ctx = JarContext {
+            path: path.display().to_string(),
+            name: path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+            depth: 0,
+            file_size: data.len() as u64,
+            entries: Vec::new(),
+            metadata: JarMetadata::default(),
+            hashes: FileHashes::from_bytes(&data),
+            constants: ConstantIndex::default(),
+            disguise: None,
+            tampering: Vec::new(),
+            abuse: Vec::new(),
+            launch,
+            version,
+            modified: None,
+        };
+        let allowed = self.allowlist.find(&ctx).map(|entry| entry.reason.clone());
+        if let Some(threat) = self.build_threat(ctx, allowed, MixinReport::default(), ObfuscationReport::default()) {
+            scan.threats.push(threat);
         }
+        scan
+    }
 
-        // Проверяем каждый чит из базы
-        for (cheat_name, cheat_info) in &self.database {
-            // Проверка исключений для strict режима - СНАЧАЛА!
-            if cheat_info.strict_mode
-                && !cheat_info.exclude_dirs.is_empty()
-                && self.has_legit_libraries(&file_list, &cheat_info.exclude_dirs) {
-                continue;
+    fn check_file(
+        &self,
+        jar_path: &Path,
+        launch: Option<String>,
+        version: Vec<String>,
+        expected: Option<ExpectedHash>,
+    ) -> FileScan {
+        let mut scan = FileScan::default();
+
+        // Файл читается один раз: и для хешей, и для архива
+        let data = match std::fs::read(jar_path) {
+            Ok(data) => data,
+            Err(e) => {
+                scan.errors.push(ScanError::from_io(jar_path, &e));
+                return scan;
             }
+        };
+        let name = jar_path.file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let disguise = if sniff::is_archive(&data) { sniff::disguise_reason(&name) } else { None };
 
-            // КРИТЕРИЙ 1: Проверка директории (ГЛАВНЫЙ)
-            let has_directory = if !cheat_info.directories.is_empty() {
-                cheat_info.directories.iter().any(|dir| {
-                    let dir_lower = dir.to_lowercase();
-                    file_list.iter().any(|fp| fp.contains(&dir_lower))
-                })
-            } else {
-                false
-            };
+        let origin = ArchiveOrigin {
+            path: jar_path.display().to_string(),
+            name,
+            depth: 0,
+            disguise,
+            launch,
+            version,
+            expected,
+        };
+        // Бюджеты времени и распаковки общие для файла и всех вложенных архивов
+        let deadline = self.limits.deadline();
+        let mut unpack_budget = self.limits.max_uncompressed_size;
+        self.check_archive(&data, origin, None, deadline, &mut unpack_budget, &mut scan);
+        scan
+    }
 
-            // Если нет директории - сразу skip (кроме случаев где директория не указана)
-            if !cheat_info.directories.is_empty() && !has_directory {
-                continue;
+    fn check_archive(
+        &self,
+        data: &[u8],
+        origin: ArchiveOrigin,
+        inherited_allow: Option<&str>,
+        deadline: Instant,
+        unpack_budget: &mut u64,
+        scan: &mut FileScan,
+    ) {
+        let limits = &self.limits;
+        // Нарушенные лимиты. После любого из них архив дальше не разбирается,
+        // кроме слишком глубокой вложенности - она только не даёт спуститься ниже
+        let mut abuse = Vec::new();
+
+        // Структура проверяется до открытия: сломанный каталог не повод пропускать jar.
+        // Если каталог не читается или врёт, анализируется архив из локальных заголовков
+        let structure = zipcheck::inspect(data, limits.max_uncompressed_size);
+        if structure.zip64 {
+            scan.errors.push(ScanError::new(ScanErrorKind::Unsupported, origin.path.clone(),
+                "ZIP64 не поддерживается: структура архива не проверена"));
+        }
+        if structure.budget_exceeded {
+            abuse.push(format!("Разбор структуры упёрся в лимит распаковки {} MB",
+                limits.max_uncompressed_size / 1024 / 1024));
+        }
+        let source = structure.recovered.as_deref().unwrap_or(data);
+        let mut archive = match ZipArchive::new(Cursor::new(source)) {
+            Ok(archive) => archive,
+            Err(e) => {
+                scan.errors.push(ScanError::new(ScanErrorKind::CorruptArchive, origin.path, e.to_string()));
+                return;
             }
+        };
 
-            // КРИТЕРИЙ 2: Проверка класса (ВАЖНЫЙ)
-            let has_class = if !cheat_info.classes.is_empty() {
-                cheat_info.classes.iter().any(|class| {
-                    let class_lower = class.to_lowercase();
-                    file_list.iter().any(|fp| fp.contains(&class_lower))
-                })
-            } else {
-                false
-            };
+        if archive.len() > limits.max_entries {
+            abuse.push(format!("Записей: {} (лимит {})", archive.len(), limits.max_entries));
+        }
 
-            // КРИТЕРИЙ 3: Проверка веса (ВСПОМОГАТЕЛЬНЫЙ, только если есть директория)
-            let has_weight = if !cheat_info.sizes_kb.is_empty() && has_directory {
-                self.check_weight_match(file_size_kb, &cheat_info.sizes_kb, 0.05)
-            } else {
-                false
+        // Собираем все имена файлов в архиве
+        let mut file_list = Vec::with_capacity(archive.len().min(limits.max_entries));
+        let mut nested = Vec::new();
+        let mut total_size: u64 = 0;
+        let mut unreadable: Vec<String> = Vec::new();
+        for i in 0..archive.len() {
+            if !abuse.is_empty() {
+                break;
+            }
+            if Instant::now() > deadline {
+                abuse.push(time_budget_message(limits));
+                break;
+            }
+            let mut file = match archive.by_index(i) {
+                Ok(file) => file,
+                Err(e) => {
+                    unreadable.push(e.to_string());
+                    continue;
+                }
             };
+            let entry = normalize_entry(file.name());
 
-            // ЛОГИКА ОПРЕДЕЛЕНИЯ УГРОЗЫ:
-            let is_threat = if cheat_info.strict_mode {
-                // Strict: Директория + Класс обязательны
-                has_directory && has_class
-            } else {
-                // Normal: Гибкая логика
-                if has_directory && has_class {
-                    // Идеально: директория + класс
-                    true
-                } else if has_directory && has_weight {
-                    // Хорошо: директория + вес
-                    true
-                } else if has_directory && cheat_info.classes.is_empty() {
-                    // OK: директория есть, классы не заданы
-                    true
-                } else if cheat_info.directories.is_empty() && has_class {
-                    // Редкий случай: нет директорий в базе, но есть класс
-                    true
-                } else {
-                    false
+            total_size = total_size.saturating_add(file.size());
+            if total_size > limits.max_uncompressed_size {
+                abuse.push(format!("Распакованный размер больше {} MB",
+                    limits.max_uncompressed_size / 1024 / 1024));
+            }
+            // Нулевой сжатый размер при ненулевом распакованном - степень сжатия бесконечна
+            match file.size().checked_div(file.compressed_size()) {
+                Some(ratio) if file.size() >= RATIO_MIN_SIZE && ratio > limits.max_compression_ratio => {
+                    abuse.push(format!("Степень сжатия 1:{} у {} (лимит 1:{})",
+                        ratio, entry, limits.max_compression_ratio));
                 }
-            };
+                None if file.size() > 0 => {
+                    abuse.push(format!("Сжатый размер 0 при распакованном {} байт у {}", file.size(), entry));
+                }
+                _ => {}
+            }
 
-            if is_threat {
-                // Считаем score для информации
-                let mut match_score = 0;
-                if has_directory { match_score += 1; }
-                if has_class { match_score += 1; }
-                if has_weight { match_score += 1; }
-
-                return Some(ThreatResult {
-                    path: jar_path.display().to_string(),
-                    name: jar_path.file_name()?.to_str()?.to_string(),
-                    size: file_size,
-                    cheat_type: cheat_name.clone(),
-                    details: vec![
-                        cheat_info.description.clone(),
-                        format!("{:.1} KB", file_size_kb),
-                        format!("Совпадений: {}/3", match_score),
-                    ],
-                    match_score,
+            // Fabric: META-INF/jars/, Forge: META-INF/jarjar/, но смотрим любые .jar,
+            // а также записи с ZIP-сигнатурой под другим расширением
+            if abuse.is_empty() && file.is_file() {
+                let is_archive = entry.ends_with(".jar") || (!entry.ends_with(".class")
+                    && file.size() >= MIN_SNIFF_SIZE
+                    && starts_with_zip_magic(&mut file));
+                if is_archive {
+                    nested.push((i, file.name().to_string(), file.size()));
+                }
+            }
+            file_list.push(entry);
+        }
+        file_list.extend(structure.central_only.iter().map(|name| normalize_entry(name)));
+        if let Some(first) = unreadable.first() {
+            scan.errors.push(ScanError::new(
+                ScanErrorKind::CorruptArchive,
+                origin.path.clone(),
+                format!("Не читается записей: {} ({})", unreadable.len(), first),
+            ));
+        }
+
+        // Тяжёлые этапы только для архива в пределах лимитов и бюджета времени
+        let within_budget = |abuse: &mut Vec<String>| {
+            if abuse.is_empty() && Instant::now() > deadline {
+                abuse.push(time_budget_message(limits));
+            }
+            abuse.is_empty()
+        };
+        let metadata = if within_budget(&mut abuse) { JarMetadata::read(&mut archive) } else { JarMetadata::default() };
+        let mixins = if within_budget(&mut abuse) { mixin::analyze(&mut archive, deadline) } else { MixinReport::default() };
+        // Сам Minecraft обфусцирован, но не проверяется по обфускации только jar клиента,
+        // чей SHA-1 совпал с json версии: класс Main от игры в jar из mods/ ничего не доказывает
+        let hashes = FileHashes::from_bytes(data);
+        let verified_game_jar = origin.expected.as_ref()
+            .is_some_and(|expected| expected.client && expected.sha1.eq_ignore_ascii_case(&hashes.sha1));
+        let obfuscation = if !verified_game_jar && within_budget(&mut abuse) {
+            obfuscation::analyze(&mut archive, deadline)
+        } else {
+            ObfuscationReport::default()
+        };
+        let constants = if self.needs_constants && within_budget(&mut abuse) {
+            ConstantIndex::build(&mut archive, deadline)
+        } else {
+            ConstantIndex::default()
+        };
+        // Этапы выше обрываются по тому же сроку: недоразобранный архив - тоже нарушение
+        within_budget(&mut abuse);
+
+        let mut ctx = JarContext {
+            path: origin.path,
+            name: origin.name,
+            depth: origin.depth,
+            file_size: data.len() as u64,
+            entries: file_list,
+            metadata,
+            hashes,
+            constants,
+            disguise: origin.disguise,
+            tampering: structure.tampering,
+            abuse: Vec::new(),
+            launch: origin.launch,
+            version: origin.version,
+            modified: None,
+        };
+        ctx.modified = origin.expected.as_ref()
+            .and_then(|expected| integrity::inspect(expected, &ctx.hashes.sha1, &ctx.entries));
+
+        // Allowlist проверяется до сигнатур; сработавшие сигнатуры всё равно
+        // попадают в результат, но с пометкой suppressed
+        let entry = self.allowlist.find(&ctx);
+        let allowed = entry
+            .map(|entry| entry.reason.clone())
+            .or_else(|| inherited_allow.map(str::to_string));
+        // Вложенным архивам разрешение передаётся только по SHA-256: fabric.mod.json
+        // с нужными id и версией копируется в любую обёртку вокруг чита
+        let nested_allow = match entry {
+            Some(entry) if entry.sha256.is_some() => Some(entry.reason.as_str()),
+            _ => inherited_allow,
+        };
+
+        if !nested.is_empty() && ctx.depth >= limits.max_nesting_depth {
+            abuse.push(format!("Вложенность jar глубже {} уровней", limits.max_nesting_depth));
+            nested.clear();
+        }
+        for (index, entry_name, size) in nested {
+            if !within_budget(&mut abuse) {
+                break;
+            }
+            if size > *unpack_budget {
+                abuse.push(format!("Вложенные архивы вместе больше {} MB",
+                    limits.max_uncompressed_size / 1024 / 1024));
+                break;
+            }
+            let inner_path = format!("{}!/{}", ctx.path, entry_name);
+            let mut inner_data = Vec::with_capacity(size as usize);
+            // Читаем на байт больше заявленного: так видно запись, которая распаковывается
+            // больше, чем записано в заголовке, и маленький заявленный размер не обходит лимиты
+            let read = archive.by_index(index)
+                .map_err(|e| e.to_string())
+                .and_then(|file| {
+                    file.take(size + 1)
+                        .read_to_end(&mut inner_data)
+                        .map_err(|e| e.to_string())
                 });
+            *unpack_budget = unpack_budget.saturating_sub(inner_data.len() as u64);
+            if inner_data.len() as u64 > size {
+                abuse.push(format!("Вложенный архив {} распаковывается больше заявленных {} байт", entry_name, size));
+                break;
+            }
+            if let Err(e) = read {
+                scan.errors.push(ScanError::new(ScanErrorKind::CorruptArchive, inner_path, e));
+                continue;
             }
+
+            let inner_name = entry_name.rsplit('/').next().unwrap_or(&entry_name).to_string();
+            let disguise = if sniff::is_archive(&inner_data) { sniff::disguise_reason(&inner_name) } else { None };
+            let inner = ArchiveOrigin {
+                path: inner_path,
+                name: inner_name,
+                depth: ctx.depth + 1,
+                disguise,
+                launch: None,
+                version: Vec::new(),
+                expected: None,
+            };
+            self.check_archive(&inner_data, inner, nested_allow, deadline, unpack_budget, scan);
         }
 
-        None
+        // Архив за лимитами проверен не полностью - это и находка, и ошибка
+        if !abuse.is_empty() {
+            scan.errors.push(ScanError::new(ScanErrorKind::LimitExceeded, ctx.path.clone(), abuse.join("; ")));
+        }
+        ctx.abuse = abuse;
+        if !ctx.metadata.mods.is_empty() {
+            scan.jars.push(JarRecord {
+                path: ctx.path.clone(),
+                name: ctx.name.clone(),
+                sha256: ctx.hashes.sha256.clone(),
+                mods: ctx.metadata.mods.clone(),
+            });
+        }
+        if let Some(threat) = self.build_threat(ctx, allowed, mixins, obfuscation) {
+            scan.threats.push(threat);
+        }
     }
 
-    fn check_weight_match(&self, file_size_kb: f32, cheat_sizes: &[f32], tolerance: f32) -> bool {
-        cheat_sizes.iter().any(|&target_size| {
-            let min_size = target_size * (1.0 - tolerance);
-            let max_size = target_size * (1.0 + tolerance);
-            file_size_kb >= min_size && file_size_kb <= max_size
-        })
-    }
+    fn build_threat(
+        &self,
+        ctx: JarContext,
+        suppressed: Option<String>,
+        mixins: MixinReport,
+        obfuscation: ObfuscationReport,
+    ) -> Option<ThreatResult> {
+        let mut matches = self.match_signatures(&ctx);
 
-    fn has_legit_libraries(&self, file_list: &[String], exclude_dirs: &[String]) -> bool {
-        if exclude_dirs.is_empty() {
-            return false;
+        let mut details = vec![format!("{:.1} KB", ctx.size_kb())];
+        if ctx.depth > 0 {
+            details.push(format!("Вложенный jar (уровень {})", ctx.depth));
         }
-        let excludes_lc: Vec<String> = exclude_dirs.iter().map(|e| e.to_lowercase()).collect();
-        file_list.iter().any(|filepath| {
-            excludes_lc.iter().any(|exclude| filepath.contains(exclude))
-        })
+
+        // Сигнатур нет - остаётся только эвристика: "подозрительный, неизвестный".
+        // Вид находки по самому сильному признаку (при равенстве - по порядку), score суммируется
+        if matches.is_empty() {
+            let mut signals: Vec<(FindingKind, &str, String, u32)> = Vec::new();
+            if let Some(ref launch) = ctx.launch {
+                signals.push((FindingKind::LaunchInjection, "Внедрение при запуске", launch.clone(), LAUNCH_SCORE));
+            }
+            if let Some(first) = ctx.version.first() {
+                signals.push((FindingKind::CustomVersion, "Нестандартная версия", first.clone(), VERSION_SCORE));
+            }
+            if let Some(ref modified) = ctx.modified {
+                signals.push((FindingKind::ModifiedJar, "Изменённый jar игры",
+                    modified.details[0].clone(), modified.score));
+            }
+            if let Some(first) = ctx.abuse.first() {
+                signals.push((FindingKind::AbusiveArchive, "Опасный архив", first.clone(), ABUSE_SCORE));
+            }
+            if let Some(first) = ctx.tampering.first() {
+                signals.push((FindingKind::TamperedArchive, "Подделанный архив", first.clone(), TAMPERING_SCORE));
+            }
+            if let Some(ref reason) = ctx.disguise {
+                signals.push((FindingKind::DisguisedArchive, "Замаскированный архив", reason.clone(), DISGUISE_SCORE));
+            }
+            if obfuscation.is_suspicious() {
+                signals.push((FindingKind::Obfuscation, "Неизвестный обфусцированный jar",
+                    "Признаки обфускации, сигнатура не найдена".to_string(), obfuscation.score));
+            }
+            if mixins.is_suspicious() {
+                signals.push((FindingKind::MixinHeuristic, "Подозрительные Mixin",
+                    "Mixin в чувствительные классы Minecraft".to_string(), mixins.score));
+            }
+
+            let score = signals.iter().map(|s| s.3).sum::<u32>().min(100);
+            let (kind, title, description, _) = signals.into_iter().rev().max_by_key(|s| s.3)?;
+            details.insert(0, description);
+            details.push(format!("Эвристический score: {}", score));
+            details.extend(ctx.abuse.iter().skip(usize::from(kind == FindingKind::AbusiveArchive)).cloned());
+            details.extend(ctx.tampering.iter().skip(usize::from(kind == FindingKind::TamperedArchive)).cloned());
+            details.extend(ctx.version.iter().skip(usize::from(kind == FindingKind::CustomVersion)).cloned());
+            if let Some(ref modified) = ctx.modified {
+                details.extend(modified.details.iter().skip(usize::from(kind == FindingKind::ModifiedJar)).cloned());
+            }
+            details.extend(mixins.details());
+            details.extend(obfuscation.details());
+            return Some(ThreatResult::from_context(
+                ctx,
+                kind,
+                title,
+                score,
+                details,
+                suppressed,
+            ));
+        }
+
+        let primary = matches.remove(0);
+        details.insert(0, primary.description.clone());
+        match primary.exact_hash {
+            Some(ref hash) => details.push(format!("Точное совпадение хеша: {}", hash)),
+            None => details.push(format!("Совпадений: {}/{}", primary.match_score, primary.max_score)),
+        }
+        details.extend(primary.hits.iter().cloned());
+        if let Some(ref launch) = ctx.launch {
+            details.insert(1, format!("Загружается при запуске: {}", launch));
+        }
+        details.extend(ctx.version.iter().cloned());
+        if let Some(ref modified) = ctx.modified {
+            details.extend(modified.details.iter().cloned());
+        }
+        details.extend(ctx.disguise.clone());
+        details.extend(ctx.tampering.iter().cloned());
+        details.extend(ctx.abuse.iter().cloned());
+        details.extend(mixins.details());
+        if obfuscation.is_suspicious() {
+            details.extend(obfuscation.details());
+        }
+
+        let kind = if ctx.launch.is_some() { FindingKind::LaunchInjection } else { FindingKind::Signature };
+        let mut threat = ThreatResult::from_context(
+            ctx,
+            kind,
+            &primary.cheat_type,
+            primary.confidence,
+            details,
+            suppressed,
+        );
+        threat.match_score = primary.match_score;
+        threat.exact_hash = primary.exact_hash.clone();
+        threat.matches = std::iter::once(primary).chain(matches).collect();
+        Some(threat)
+    }
+
+    // Все сработавшие сигнатуры в детерминированном порядке
+    pub fn match_signatures(&self, ctx: &JarContext) -> Vec<SignatureMatch> {
+        let mut matches: Vec<SignatureMatch> = self.database.iter()
+            .filter_map(|(cheat_name, cheat_info)| {
+                let rule = cheat_info.rule.as_ref()?;
+                let evaluation = rule.evaluate(ctx);
+
+                // Точный хеш засчитывается даже если правило не сработало
+                let exact_hash = cheat_info.hashes.iter()
+                    .find_map(|known| ctx.hashes.find(known));
+
+                if !evaluation.matched && exact_hash.is_none() {
+                    return None;
+                }
+
+                let max_score = rule.max_score();
+                let confidence = if exact_hash.is_some() {
+                    100
+                } else {
+                    (evaluation.score * 100)
+                        .checked_div(max_score)
+                        .map_or(100, |c| c.min(100) as u32)
+                };
+
+                Some(SignatureMatch {
+                    cheat_type: cheat_name.clone(),
+                    description: cheat_info.description.clone(),
+                    match_score: evaluation.score,
+                    max_score,
+                    confidence,
+                    exact_hash,
+                    hits: evaluation.hits,
+                })
+            })
+            .collect();
+
+        // Точный хеш, уверенность, абсолютный score, затем имя сигнатуры
+        matches.sort_by(|a, b| {
+            (a.exact_hash.is_none(), Reverse(a.confidence), Reverse(a.match_score), &a.cheat_type)
+                .cmp(&(b.exact_hash.is_none(), Reverse(b.confidence), Reverse(b.match_score), &b.cheat_type))
+        });
+        matches
     }
 
-    fn init_database(database: &mut HashMap<String, CheatInfo>) {
+    fn init_database(database: &mut BTreeMap<String, CheatInfo>) {
         database.insert("DoomsDay".to_string(), CheatInfo {
             directories: vec!["net/java/".to_string()],
             classes: vec!["i.class".to_string()],
@@ -198,6 +1058,22 @@ impl CheatDetector {
             description: "DoomsDay чит (опасный)".to_string(),
             strict_mode: true,  // Директория + Класс обязательны
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            // Директория + класс, и никаких легитимных библиотек рядом
+            rule: Some(Rule::All(vec![
+                Rule::Directory("net/java/".to_string()),
+                Rule::Class("i.class".to_string()),
+                Rule::Not(Box::new(Rule::Any(vec![
+                    Rule::Directory("org/apache/".to_string()),
+                    Rule::Directory("com/google/".to_string()),
+                    Rule::Directory("io/netty/".to_string()),
+                    Rule::Directory("net/minecraft/".to_string()),
+                    Rule::Directory("net/minecraftforge/".to_string()),
+                    Rule::Directory("optifine/".to_string()),
+                    Rule::Directory("javax/".to_string()),
+                ]))),
+            ])),
         });
 
         database.insert("Freecam".to_string(), CheatInfo {
@@ -208,6 +1084,9 @@ impl CheatDetector {
             description: "Freecam мод".to_string(),
             strict_mode: false,  // Гибкая проверка
             min_conditions: 2,
+            mod_ids: vec!["freecam".to_string()],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("Freecam2".to_string(), CheatInfo {
@@ -218,6 +1097,9 @@ impl CheatDetector {
             description: "Freecam мод (вариант 2)".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("NekoClient".to_string(), CheatInfo {
@@ -228,6 +1110,9 @@ impl CheatDetector {
             description: "NekoClient Ghost".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("SeedCracker".to_string(), CheatInfo {
@@ -238,6 +1123,9 @@ impl CheatDetector {
             description: "SeedCracker".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("Britva".to_string(), CheatInfo {
@@ -248,6 +1136,9 @@ impl CheatDetector {
             description: "Britva Ghost/AutoMyst".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("Inventory Move".to_string(), CheatInfo {
@@ -258,6 +1149,9 @@ impl CheatDetector {
             description: "Inventory Move".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("WorldDownloader".to_string(), CheatInfo {
@@ -268,6 +1162,9 @@ impl CheatDetector {
             description: "WorldDownloader".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("AutoBuy".to_string(), CheatInfo {
@@ -281,6 +1178,9 @@ impl CheatDetector {
             description: "AutoBuy читы".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("BedrockBricker".to_string(), CheatInfo {
@@ -291,6 +1191,9 @@ impl CheatDetector {
             description: "Bedrock Bricker мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("ViaVersion".to_string(), CheatInfo {
@@ -301,6 +1204,9 @@ impl CheatDetector {
             description: "ViaVersion мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("DoubleHotbar".to_string(), CheatInfo {
@@ -311,6 +1217,9 @@ impl CheatDetector {
             description: "Double Hotbar мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("ElytraSwap".to_string(), CheatInfo {
@@ -329,6 +1238,9 @@ impl CheatDetector {
             description: "Elytra Swap мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("ArmorHotswap".to_string(), CheatInfo {
@@ -345,6 +1257,9 @@ impl CheatDetector {
             description: "Armor Hotswap мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("ChestLocator".to_string(), CheatInfo {
@@ -355,6 +1270,9 @@ impl CheatDetector {
             description: "Chest Locator мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("TopkaAutoBuyV1".to_string(), CheatInfo {
@@ -364,7 +1282,10 @@ impl CheatDetector {
             sizes_kb: vec![48.0],
             description: "Topka AutoBuy v1 (бан за хранение)".to_string(),
             strict_mode: false,
-            min_conditions: 2,
+            min_conditions: 1,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("NoHurtCam DanilSimX.jar".to_string(), CheatInfo {
@@ -375,6 +1296,16 @@ impl CheatDetector {
             description: "NoHurtCam DanilSimX.jar хитбоксы".to_string(),
             strict_mode: false,
             min_conditions: 1,
+            mod_ids: vec![],
+            hashes: vec![],
+            // Директория + класс ML или характерный размер
+            rule: Some(Rule::All(vec![
+                Rule::Directory("nohurtcam/".to_string()),
+                Rule::Any(vec![
+                    Rule::Class("ML.class".to_string()),
+                    Rule::Size { kb: vec![95.0], tolerance: 0.05 },
+                ]),
+            ])),
         });
 
         database.insert("GUMBALLOFFMODE".to_string(), CheatInfo {
@@ -391,6 +1322,9 @@ impl CheatDetector {
             description: "GUMBALLOFFMODE мод".to_string(),
             strict_mode: true,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("LibrarianTradeFinder".to_string(), CheatInfo {
@@ -401,6 +1335,9 @@ impl CheatDetector {
             description: "Librarian Trade Finder мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("AutoAttack".to_string(), CheatInfo {
@@ -411,6 +1348,9 @@ impl CheatDetector {
             description: "Auto Attack мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("EntityOutliner".to_string(), CheatInfo {
@@ -421,6 +1361,9 @@ impl CheatDetector {
             description: "Entity Outliner мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("CameraUtils".to_string(), CheatInfo {
@@ -431,6 +1374,9 @@ impl CheatDetector {
             description: "Camera Utils мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("WallJumpTXF".to_string(), CheatInfo {
@@ -441,6 +1387,9 @@ impl CheatDetector {
             description: "Wall-Jump TXF мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("CrystalOptimizer".to_string(), CheatInfo {
@@ -451,6 +1400,9 @@ impl CheatDetector {
             description: "Crystal Optimizer мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("SoupAPI".to_string(), CheatInfo {
@@ -461,6 +1413,9 @@ impl CheatDetector {
             description: "Soup API (бан за хранение)".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("MeteorClient".to_string(), CheatInfo {
@@ -471,6 +1426,9 @@ impl CheatDetector {
             description: "Meteor Client".to_string(),
             strict_mode: false,
             min_conditions: 1,
+            mod_ids: vec!["meteor-client".to_string()],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("ClickCrystals".to_string(), CheatInfo {
@@ -481,6 +1439,9 @@ impl CheatDetector {
             description: "ClickCrystals мод".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("Ezhitboxes".to_string(), CheatInfo {
@@ -491,6 +1452,9 @@ impl CheatDetector {
             description: "Ezhitboxes хитбокс".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
         database.insert("PseudoNeat".to_string(), CheatInfo {
@@ -501,7 +1465,453 @@ impl CheatDetector {
             description: "PseudoNeat хитбокс".to_string(),
             strict_mode: false,
             min_conditions: 2,
+            mod_ids: vec![],
+            hashes: vec![],
+            rule: None,
         });
 
     }
-}
\ No newline at end of file
+}
+
+// Имя без регистра, пробелов и знаков: "Inventory Move" -> "inventorymove"
+fn compact_name(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+fn time_budget_message(limits: &ScanLimits) -> String {
+    format!("Разбор дольше {} с", limits.time_budget_secs)
+}
+
+fn starts_with_zip_magic(file: &mut impl Read) -> bool {
+    let mut head = [0u8; 4];
+    file.read_exact(&mut head).is_ok() && &head == sniff::ZIP_MAGIC
+}
+
+// Единый вид пути записи: нижний регистр, "/" как разделитель, без ведущих "/" и "./"
+pub fn normalize_entry(name: &str) -> String {
+    let lower = name.to_lowercase().replace('\\', "/");
+    let mut trimmed = lower.as_str();
+    loop {
+        if let Some(rest) = trimmed.strip_prefix('/') {
+            trimmed = rest;
+        } else if let Some(rest) = trimmed.strip_prefix("./") {
+            trimmed = rest;
+        } else {
+            break;
+        }
+    }
+    trimmed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::allowlist::AllowEntry;
+    use crate::testutil::{jar, ClassBuilder};
+
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn log_file(name: &str, text: &str) -> PathBuf {
+        temp_file(&format!("{}.log", name), text.as_bytes())
+    }
+
+    // Короткие имена классов и Main от Minecraft, как у jar клиента
+    fn obfuscated_game_jar() -> Vec<u8> {
+        let names: Vec<String> = ["net/minecraft/client/main/Main".to_string()].into_iter()
+            .chain((b'a'..=b'l').map(|c| (c as char).to_string()))
+            .collect();
+        let classes: Vec<(String, Vec<u8>)> = names.iter()
+            .map(|name| (format!("{}.class", name), ClassBuilder::new(name).build()))
+            .collect();
+        let entries: Vec<(&str, &[u8])> = classes.iter().map(|(n, d)| (n.as_str(), d.as_slice())).collect();
+        jar(&entries)
+    }
+
+    fn version(jar_path: &Path, sha1: &str) -> VersionInfo {
+        VersionInfo {
+            name: "1.20.1".to_string(),
+            json_path: jar_path.with_extension("json"),
+            jar_path: Some(jar_path.to_path_buf()),
+            main_class: Some("net.minecraft.client.main.Main".to_string()),
+            inherits_from: None,
+            unknown_libraries: Vec::new(),
+            unknown_library_jars: Vec::new(),
+            client_hash: Some(ExpectedHash {
+                sha1: sha1.to_string(),
+                source: "versions/1.20.1/1.20.1.json: client".to_string(),
+                client: true,
+            }),
+            libraries: Vec::new(),
+        }
+    }
+
+    fn hitbox_jar(size_kb: u64) -> JarContext {
+        JarContext {
+            entries: vec!["me/bushroot/hb/modules/hitbox.class".to_string()],
+            file_size: size_kb * 1024,
+            ..JarContext::default()
+        }
+    }
+
+    #[test]
+    fn all_matches_in_stable_order() {
+        let detector = CheatDetector::new();
+        // Одинаковая уверенность (директория + класс) - порядок по имени сигнатуры
+        let names: Vec<String> = detector.match_signatures(&hitbox_jar(2)).into_iter().map(|m| m.cheat_type).collect();
+        assert_eq!(names, ["Ezhitboxes", "PseudoNeat"]);
+        // Размер совпал только у PseudoNeat - она первая
+        let matches = detector.match_signatures(&hitbox_jar(17));
+        assert_eq!((matches[0].cheat_type.as_str(), matches[0].confidence), ("PseudoNeat", 100));
+        assert_eq!((matches[1].cheat_type.as_str(), matches[1].confidence), ("Ezhitboxes", 66));
+    }
+
+    #[test]
+    fn threat_lists_other_candidates() {
+        let threat = CheatDetector::new().build_threat(hitbox_jar(17), None, MixinReport::default(),
+            ObfuscationReport::default()).unwrap();
+        assert_eq!((threat.kind, threat.cheat_type.as_str()), (FindingKind::Signature, "PseudoNeat"));
+        let candidates: Vec<&str> = threat.matches.iter().map(|m| m.cheat_type.as_str()).collect();
+        assert_eq!(candidates, ["PseudoNeat", "Ezhitboxes"]);
+    }
+
+    #[test]
+    fn clean_jar_keeps_mod_metadata() {
+        let data = jar(&[
+            ("fabric.mod.json", br#"{"schemaVersion": 1, "id": "sodium", "version": "0.5.3", "name": "Sodium"}"#),
+            ("me/jellysquid/Sodium.class", &ClassBuilder::new("me/jellysquid/Sodium").build()),
+        ]);
+        let path = temp_file("sodium.jar", &data);
+        let scan = CheatDetector::new().check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(scan.threats.is_empty());
+        assert_eq!(scan.jars.len(), 1);
+        assert!(scan.jars[0].name.ends_with("sodium.jar"));
+        let m = &scan.jars[0].mods[0];
+        assert_eq!((m.loader.as_str(), m.id.as_str(), m.version.as_deref()), ("fabric", "sodium", Some("0.5.3")));
+    }
+
+    #[test]
+    fn disguised_jar_is_a_finding() {
+        let data = jar(&[("me/jellysquid/Sodium.class", &ClassBuilder::new("me/jellysquid/Sodium").build())]);
+        let path = temp_file("skin.png", &data);
+        let scan = CheatDetector::new().check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert_eq!(threat.kind, FindingKind::DisguisedArchive);
+        assert_eq!(threat.details[0], "Расширение .png скрывает ZIP-архив");
+    }
+
+    fn wrapped_cheat() -> Vec<u8> {
+        let cheat = jar(&[("me/bushroot/hb/modules/HitBox.class", &ClassBuilder::new("me/bushroot/hb/modules/HitBox").build())]);
+        jar(&[
+            ("fabric.mod.json", br#"{"schemaVersion": 1, "id": "sodium", "version": "0.5.3"}"#),
+            ("META-INF/jars/cheat.jar", &cheat),
+        ])
+    }
+
+    fn allow(sha256: Option<String>, mod_id: Option<&str>) -> Allowlist {
+        Allowlist {
+            entries: vec![AllowEntry {
+                sha256,
+                mod_id: mod_id.map(str::to_string),
+                version: mod_id.map(|_| "0.5.3".to_string()),
+                reason: "тест".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn nested_cheat_is_not_hidden_by_wrapper_mod_id() {
+        let path = temp_file("allowed-by-id.jar", &wrapped_cheat());
+        let scan = CheatDetector::new().with_allowlist(allow(None, Some("sodium"))).check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert!(threat.path.ends_with("allowed-by-id.jar!/META-INF/jars/cheat.jar"));
+        assert_eq!(threat.cheat_type, "Ezhitboxes");
+        assert_eq!(threat.suppressed, None);
+    }
+
+    #[test]
+    fn wrapper_allowed_by_hash_covers_nested_jars() {
+        let data = wrapped_cheat();
+        let path = temp_file("allowed-by-hash.jar", &data);
+        let sha256 = FileHashes::from_bytes(&data).sha256;
+        let scan = CheatDetector::new().with_allowlist(allow(Some(sha256), None)).check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        assert_eq!(scan.threats[0].suppressed.as_deref(), Some("тест"));
+    }
+
+    fn scan_with_limits(name: &str, data: &[u8], limits: ScanLimits) -> FileScan {
+        let path = temp_file(name, data);
+        let scan = CheatDetector::new().with_limits(limits).check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        scan
+    }
+
+    // Единственная находка - опасный архив, и он же в ошибках как недопроверенный
+    fn assert_abuse(scan: &FileScan, message: &str) {
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert_eq!(threat.kind, FindingKind::AbusiveArchive);
+        assert!(threat.details.iter().any(|d| d.contains(message)), "{:?}", threat.details);
+        assert!(scan.errors.iter().any(|e| e.kind == ScanErrorKind::LimitExceeded && e.message.contains(message)));
+    }
+
+    // Меняет распакованный размер записи в локальном заголовке и в каталоге
+    fn declare_size(data: &mut [u8], name: &[u8], size: u32) {
+        for (magic, name_at, size_at) in [(b"PK\x03\x04", 30, 22), (b"PK\x01\x02", 46, 24)] {
+            let pos = (0..data.len() - name_at - name.len())
+                .find(|&pos| &data[pos..pos + 4] == magic && &data[pos + name_at..pos + name_at + name.len()] == name)
+                .unwrap();
+            data[pos + size_at..pos + size_at + 4].copy_from_slice(&size.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn too_many_entries_is_abuse() {
+        let data = jar(&[("a.txt", b"a"), ("b.txt", b"b"), ("c.txt", b"c")]);
+        let scan = scan_with_limits("entries.jar", &data, ScanLimits { max_entries: 2, ..ScanLimits::default() });
+        assert_abuse(&scan, "Записей: 3 (лимит 2)");
+    }
+
+    #[test]
+    fn total_size_over_limit_is_abuse() {
+        let data = jar(&[("a.txt", &[b'a'; 600]), ("b.txt", &[b'b'; 600])]);
+        let scan = scan_with_limits("total.jar", &data, ScanLimits { max_uncompressed_size: 1000, ..ScanLimits::default() });
+        assert_abuse(&scan, "Распакованный размер больше");
+    }
+
+    #[test]
+    fn compression_ratio_over_limit_is_abuse() {
+        let data = jar(&[("zeros.bin", &vec![0u8; 2 * RATIO_MIN_SIZE as usize])]);
+        let scan = scan_with_limits("ratio.jar", &data, ScanLimits::default());
+        assert_abuse(&scan, "Степень сжатия");
+    }
+
+    #[test]
+    fn nesting_over_limit_is_abuse() {
+        let inner = jar(&[("a.txt", b"a")]);
+        let middle = jar(&[("META-INF/jars/inner.jar", &inner)]);
+        let outer = jar(&[("META-INF/jars/middle.jar", &middle)]);
+        let scan = scan_with_limits("depth.jar", &outer, ScanLimits { max_nesting_depth: 1, ..ScanLimits::default() });
+        assert_abuse(&scan, "Вложенность jar глубже 1 уровней");
+        assert!(scan.threats[0].path.ends_with("depth.jar!/META-INF/jars/middle.jar"));
+    }
+
+    #[test]
+    fn time_budget_is_abuse() {
+        let data = jar(&[("a.txt", b"a")]);
+        let scan = scan_with_limits("slow.jar", &data, ScanLimits { time_budget_secs: 0, ..ScanLimits::default() });
+        assert_abuse(&scan, "Разбор дольше 0 с");
+    }
+
+    #[test]
+    fn nested_jar_larger_than_declared_is_abuse() {
+        let inner = jar(&[("a.txt", &[b'a'; 4096])]);
+        let mut outer = jar(&[("META-INF/jars/inner.jar", &inner)]);
+        declare_size(&mut outer, b"META-INF/jars/inner.jar", 10);
+        let scan = scan_with_limits("understated.jar", &outer, ScanLimits::default());
+        assert_abuse(&scan, "Вложенный архив META-INF/jars/inner.jar распаковывается больше заявленных 10 байт");
+    }
+
+    #[test]
+    fn nested_jars_share_unpack_budget() {
+        let inner = jar(&[("a.txt", b"a")]);
+        let middle = jar(&[("META-INF/jars/inner.jar", &inner)]);
+        let outer = jar(&[("META-INF/jars/middle.jar", &middle)]);
+        // Каждый уровень в лимите, но вместе с middle.jar распаковать inner.jar уже нельзя
+        let limit = (middle.len() + inner.len() / 2) as u64;
+        let scan = scan_with_limits("budget.jar", &outer, ScanLimits { max_uncompressed_size: limit, ..ScanLimits::default() });
+        assert_abuse(&scan, "Вложенные архивы вместе больше");
+        assert!(scan.threats[0].path.ends_with("budget.jar!/META-INF/jars/middle.jar"));
+    }
+
+    fn custom_database(replace_builtin: bool) -> SignatureDatabase {
+        let mut signatures = BTreeMap::new();
+        signatures.insert("Custom".to_string(), CheatInfo {
+            directories: Vec::new(),
+            classes: Vec::new(),
+            exclude_dirs: Vec::new(),
+            sizes_kb: Vec::new(),
+            description: "Свой чит".to_string(),
+            strict_mode: false,
+            min_conditions: 1,
+            mod_ids: vec!["customcheat".to_string()],
+            hashes: Vec::new(),
+            rule: None,
+        });
+        SignatureDatabase {
+            version: "test-1".to_string(),
+            replace_builtin,
+            signatures,
+            limits: Some(ScanLimits { max_entries: 10, ..ScanLimits::default() }),
+        }
+    }
+
+    fn mod_jar(id: &str) -> JarContext {
+        let metadata = JarMetadata {
+            mods: vec![ModMetadata { loader: "fabric".to_string(), id: id.to_string(), ..ModMetadata::default() }],
+            ..JarMetadata::default()
+        };
+        JarContext { metadata, ..JarContext::default() }
+    }
+
+    #[test]
+    fn toml_database_extends_builtin() {
+        let path = temp_file("signatures.toml", toml::to_string(&custom_database(false)).unwrap().as_bytes());
+        let detector = CheatDetector::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(detector.signature_count(), CheatDetector::new().signature_count() + 1);
+        assert_eq!(detector.database_version(), "test-1");
+        assert_eq!(detector.database_source(), Some(path.as_path()));
+        assert_eq!(detector.limits.max_entries, 10);
+        assert_eq!(detector.match_signatures(&mod_jar("customcheat"))[0].cheat_type, "Custom");
+        assert_eq!(detector.match_signatures(&mod_jar("freecam"))[0].cheat_type, "Freecam");
+    }
+
+    #[test]
+    fn json_database_replaces_builtin() {
+        let path = temp_file("signatures.json", serde_json::to_string(&custom_database(true)).unwrap().as_bytes());
+        let detector = CheatDetector::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(detector.signature_count(), 1);
+        assert_eq!(detector.match_signatures(&mod_jar("customcheat"))[0].cheat_type, "Custom");
+        assert!(detector.match_signatures(&mod_jar("freecam")).is_empty());
+    }
+
+    #[test]
+    fn malformed_database_is_an_error() {
+        let path = temp_file("broken.toml", b"[signatures.Custom\ndescription = 1");
+        let error = CheatDetector::from_file(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.starts_with(&path.display().to_string()), "{}", error);
+
+        let missing = std::env::temp_dir().join("sbcheats-missing-signatures.json");
+        assert!(SignatureDatabase::from_file(&missing).is_err());
+    }
+
+    #[test]
+    fn nested_chain_path_and_level() {
+        let cheat = jar(&[("me/bushroot/hb/modules/HitBox.class", &ClassBuilder::new("me/bushroot/hb/modules/HitBox").build())]);
+        let middle = jar(&[("META-INF/jarjar/inner.jar", &cheat)]);
+        let outer = jar(&[("META-INF/jars/middle.jar", &middle)]);
+        let path = temp_file("outer.jar", &outer);
+        let scan = CheatDetector::new().check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert!(threat.path.ends_with("outer.jar!/META-INF/jars/middle.jar!/META-INF/jarjar/inner.jar"), "{}", threat.path);
+        assert_eq!((threat.name.as_str(), threat.cheat_type.as_str()), ("inner.jar", "Ezhitboxes"));
+        assert!(threat.details.iter().any(|d| d == "Вложенный jar (уровень 2)"));
+    }
+
+    #[test]
+    fn default_depth_limit_is_abuse() {
+        let depth = ScanLimits::default().max_nesting_depth;
+        let mut data = jar(&[("a.txt", b"a")]);
+        for _ in 0..=depth {
+            data = jar(&[("META-INF/jars/inner.jar", &data)]);
+        }
+        let path = temp_file("deep.jar", &data);
+        let scan = CheatDetector::new().check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert_eq!(threat.kind, FindingKind::AbusiveArchive);
+        assert_eq!(threat.path.matches("!/").count(), depth);
+        assert_eq!(threat.details[0], format!("Вложенность jar глубже {} уровней", depth));
+    }
+
+    #[test]
+    fn game_main_class_does_not_exempt_mods() {
+        let data = obfuscated_game_jar();
+        let path = temp_file("game-in-mods.jar", &data);
+        let scan = CheatDetector::new().check_jar_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        assert_eq!(scan.threats[0].kind, FindingKind::Obfuscation);
+    }
+
+    #[test]
+    fn only_verified_client_jar_skips_obfuscation() {
+        let data = obfuscated_game_jar();
+        let path = temp_file("1.20.1.jar", &data);
+        let detector = CheatDetector::new();
+        let verified = detector.check_version(&version(&path, &FileHashes::from_bytes(&data).sha1));
+        let modified = detector.check_version(&version(&path, &"0".repeat(40)));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(verified.threats.is_empty());
+        assert_eq!(modified.threats.len(), 1);
+        assert_eq!(modified.threats[0].kind, FindingKind::ModifiedJar);
+        assert!(modified.threats[0].details.iter().any(|d| d.starts_with("Короткие имена классов")));
+    }
+
+    #[test]
+    fn missing_agent_is_a_launch_finding() {
+        let reference = LaunchReference {
+            kind: InjectionKind::JavaAgent,
+            value: "agents/deleted.jar".to_string(),
+            source: "JAVA_TOOL_OPTIONS".to_string(),
+            resolved: None,
+        };
+        let scan = CheatDetector::new().check_launch(&reference);
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert_eq!((threat.kind, threat.name.as_str()), (FindingKind::LaunchInjection, "deleted.jar"));
+        assert!(threat.is_heuristic());
+        assert_eq!(threat.details[0], "-javaagent agents/deleted.jar (JAVA_TOOL_OPTIONS)");
+        assert_eq!(scan.errors.len(), 1);
+        assert_eq!(scan.errors[0].kind, ScanErrorKind::Io);
+    }
+
+    #[test]
+    fn log_matches_stack_frames_but_not_chat() {
+        let path = log_file("frames", "[12:00:00] [Render thread/INFO]: [CHAT] net.java.i\n\
+            [12:00:01] [Render thread/ERROR]: Crash\n\tat net.java.i.run(i.java:42) ~[?:?]\n");
+        let scan = CheatDetector::new().check_log(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        let threat = &scan.threats[0];
+        assert_eq!((threat.kind, threat.cheat_type.as_str()), (FindingKind::LogEvidence, "DoomsDay"));
+        assert!(threat.path.ends_with(":3"));
+        assert!(threat.hashes.is_empty());
+    }
+
+    #[test]
+    fn allowlist_does_not_hide_log_evidence() {
+        let path = log_file("allowlist", "[12:00:00] [main/INFO]: Loading 1 mods:\n\t- freecam 1.2.0\n");
+        let allowlist = Allowlist {
+            entries: vec![AllowEntry {
+                sha256: None,
+                mod_id: Some("freecam".to_string()),
+                version: Some("1.2.0".to_string()),
+                reason: "тест".to_string(),
+            }],
+        };
+        let scan = CheatDetector::new().with_allowlist(allowlist).check_log(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(scan.threats.len(), 1);
+        assert_eq!(scan.threats[0].cheat_type, "Freecam");
+        assert_eq!(scan.threats[0].suppressed, None);
+    }
+}
@@ -0,0 +1,69 @@
+// errors.rs - Ошибки сканирования отдельных файлов
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanErrorKind {
+    Io,
+    AccessDenied,
+    CorruptArchive,
+    LimitExceeded,
+    // Законный формат, который сканер проверяет не полностью (ZIP64)
+    Unsupported,
+}
+
+impl ScanErrorKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ScanErrorKind::Io => "Ошибка чтения",
+            ScanErrorKind::AccessDenied => "Нет доступа",
+            ScanErrorKind::CorruptArchive => "Повреждённый архив",
+            ScanErrorKind::LimitExceeded => "Превышен лимит",
+            ScanErrorKind::Unsupported => "Не поддерживается",
+        }
+    }
+}
+
+// Файл (или вложенный архив), который не удалось проверить целиком
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanError {
+    pub kind: ScanErrorKind,
+    // Путь на диске или цепочка "a.jar!/inner.jar"
+    pub path: String,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn new(kind: ScanErrorKind, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn from_io(path: &Path, error: &io::Error) -> Self {
+        let kind = if error.kind() == io::ErrorKind::PermissionDenied {
+            ScanErrorKind::AccessDenied
+        } else {
+            ScanErrorKind::Io
+        };
+        Self::new(kind, path.display().to_string(), error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_denied_is_access_denied() {
+        let path = Path::new("mods/locked.jar");
+        let denied = ScanError::from_io(path, &io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!((denied.kind, denied.path.as_str()), (ScanErrorKind::AccessDenied, "mods/locked.jar"));
+        let missing = ScanError::from_io(path, &io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(missing.kind, ScanErrorKind::Io);
+    }
+}
@@ -0,0 +1,136 @@
+// hashes.rs - Хеши файлов для точных сигнатур
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHashes {
+    pub sha256: String,
+    pub sha1: String,
+    // Fingerprint CurseForge (murmur2 без пробельных байтов)
+    pub murmur2: u32,
+}
+
+impl FileHashes {
+    pub fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            sha256: to_hex(&Sha256::digest(data)),
+            sha1: to_hex(&Sha1::digest(data)),
+            murmur2: curseforge_fingerprint(data),
+        }
+    }
+
+    // Нет файла, по которому считать (улика из лога)
+    pub fn is_empty(&self) -> bool {
+        self.sha256.is_empty()
+    }
+
+    // Сравнение с известным хешем из базы. Тип определяется по виду строки:
+    // 64 hex - SHA-256, 40 hex - SHA-1, десятичное число - murmur2.
+    // Возвращает подпись вида "SHA-256: ..." для отчёта
+    pub fn find(&self, known: &str) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let known = known.trim();
+        let is_hex = known.chars().all(|c| c.is_ascii_hexdigit());
+
+        if known.len() == 64 && is_hex && known.eq_ignore_ascii_case(&self.sha256) {
+            Some(format!("SHA-256: {}", self.sha256))
+        } else if known.len() == 40 && is_hex && known.eq_ignore_ascii_case(&self.sha1) {
+            Some(format!("SHA-1: {}", self.sha1))
+        } else if known.parse::<u32>().ok() == Some(self.murmur2) {
+            Some(format!("Murmur2: {}", self.murmur2))
+        } else {
+            None
+        }
+    }
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// CurseForge считает MurmurHash2 (seed = 1) по файлу без байтов 9, 10, 13, 32
+pub fn curseforge_fingerprint(data: &[u8]) -> u32 {
+    let normalized: Vec<u8> = data.iter()
+        .copied()
+        .filter(|b| !matches!(b, 9 | 10 | 13 | 32))
+        .collect();
+    murmur2(&normalized, 1)
+}
+
+pub fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h = seed ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let tail = chunks.remainder();
+    if tail.len() >= 3 {
+        h ^= (tail[2] as u32) << 16;
+    }
+    if tail.len() >= 2 {
+        h ^= (tail[1] as u32) << 8;
+    }
+    if !tail.is_empty() {
+        h ^= tail[0] as u32;
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    const ABC_SHA1: &str = "a9993e364706816aba3e25717850c26c9cd0d89d";
+
+    #[test]
+    fn murmur2_known_answers() {
+        // Эталонная реализация MurmurHash2, seed = 1; хвост от 0 до 3 байт
+        assert_eq!(murmur2(b"", 1), 1540447798);
+        assert_eq!(murmur2(b"a", 1), 626045324);
+        assert_eq!(murmur2(b"ab", 1), 1692487918);
+        assert_eq!(murmur2(b"abc", 1), 1621425345);
+        assert_eq!(murmur2(b"abcd", 1), 3376380438);
+        assert_eq!(murmur2(b"Hello, world!", 1), 1671593801);
+    }
+
+    #[test]
+    fn fingerprint_skips_whitespace() {
+        assert_eq!(curseforge_fingerprint(b"hello world\r\n\t"), murmur2(b"helloworld", 1));
+        assert_eq!(curseforge_fingerprint(b" hello\tworld "), 2824650221);
+    }
+
+    #[test]
+    fn find_dispatches_by_hash_format() {
+        let hashes = FileHashes::from_bytes(b"abc");
+        assert_eq!((hashes.sha256.as_str(), hashes.sha1.as_str()), (ABC_SHA256, ABC_SHA1));
+
+        assert_eq!(hashes.find(&ABC_SHA256.to_uppercase()), Some(format!("SHA-256: {}", ABC_SHA256)));
+        assert_eq!(hashes.find(&format!(" {} ", ABC_SHA1)), Some(format!("SHA-1: {}", ABC_SHA1)));
+        assert_eq!(hashes.find("1621425345").as_deref(), Some("Murmur2: 1621425345"));
+
+        // Префикс хеша и чужой fingerprint не совпадают
+        assert_eq!(hashes.find(&ABC_SHA256[..40]), None);
+        assert_eq!(hashes.find("1621425346"), None);
+        assert_eq!(FileHashes::default().find(ABC_SHA256), None);
+    }
+}
@@ -0,0 +1,289 @@
+// injection.rs - Читы, которые грузятся не из mods/: -javaagent, -agentpath, --tweakClass
+// в аргументах профилей лаунчеров, в JAVA_TOOL_OPTIONS / _JAVA_OPTIONS и в json версий
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+use crate::launchers::Instance;
+
+// Переменные, которые JVM читает сама при старте
+const JVM_ENV_VARS: [&str; 3] = ["JAVA_TOOL_OPTIONS", "_JAVA_OPTIONS", "JDK_JAVA_OPTIONS"];
+
+// Штатные твикеры Forge, OptiFine, LiteLoader и Mixin
+const KNOWN_TWEAKERS: [&str; 9] = [
+    "net.minecraftforge.fml.common.launcher.FMLTweaker",
+    "net.minecraftforge.fml.common.launcher.FMLServerTweaker",
+    "cpw.mods.fml.common.launcher.FMLTweaker",
+    "optifine.OptiFineTweaker",
+    "optifine.OptiFineForgeTweaker",
+    "com.mumfrey.liteloader.launch.LiteLoaderTweaker",
+    "org.spongepowered.asm.launch.MixinTweaker",
+    "net.minecraft.launchwrapper.VanillaTweaker",
+    "net.minecraft.launchwrapper.AlphaVanillaTweaker",
+];
+
+// Чем заканчивается путь агента: jar для -javaagent, нативная библиотека для -agentpath
+const AGENT_SUFFIXES: [&str; 4] = [".jar", ".dll", ".so", ".dylib"];
+
+// Где искать jar с классом твикера
+const TWEAKER_SEARCH_DIRS: [&str; 3] = ["mods", "libraries", "versions"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionKind {
+    JavaAgent,
+    NativeAgent,
+    TweakClass,
+}
+
+impl InjectionKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            InjectionKind::JavaAgent => "-javaagent",
+            InjectionKind::NativeAgent => "-agentpath",
+            InjectionKind::TweakClass => "--tweakClass",
+        }
+    }
+}
+
+// Аргументы JVM из одного источника: профиль лаунчера, переменная окружения
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JavaArgs {
+    // "launcher_profiles.json: Default"
+    pub source: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchReference {
+    pub kind: InjectionKind,
+    // Как указано в аргументах: путь к агенту или имя класса
+    pub value: String,
+    pub source: String,
+    // Файл, который загрузится при запуске; None - не найден
+    pub resolved: Option<PathBuf>,
+}
+
+impl LaunchReference {
+    // "-javaagent: C:/x/agent.jar (JAVA_TOOL_OPTIONS)"
+    pub fn describe(&self) -> String {
+        format!("{} {} ({})", self.kind.label(), self.value, self.source)
+    }
+}
+
+// Все ссылки из окружения процесса, профилей инстансов и json их версий
+pub fn collect(instances: &[Instance]) -> Vec<LaunchReference> {
+    let mut references = Vec::new();
+    let cwd = std::env::current_dir().ok();
+    for var in JVM_ENV_VARS {
+        if let Ok(value) = std::env::var(var) {
+            references.extend(parse_args(&split_args(&value), var, cwd.as_deref()));
+        }
+    }
+
+    for instance in instances {
+        for java_args in &instance.java_args {
+            references.extend(parse_args(&java_args.args, &java_args.source, Some(&instance.game_dir)));
+        }
+        references.extend(version_tweakers(&instance.game_dir));
+    }
+
+    references.sort_by(|a, b| (&a.value, &a.source).cmp(&(&b.value, &b.source)));
+    references.dedup_by(|a, b| a.kind == b.kind && a.value == b.value && a.source == b.source);
+    references
+}
+
+// Аргументы после разбора строки: -javaagent:путь[=опции], -agentpath:путь[=опции],
+// --tweakClass класс и --tweakClass=класс
+pub fn parse_args(args: &[String], source: &str, base: Option<&Path>) -> Vec<LaunchReference> {
+    let mut references = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (kind, value) = if let Some(rest) = arg.strip_prefix("-javaagent:") {
+            (InjectionKind::JavaAgent, strip_agent_options(rest))
+        } else if let Some(rest) = arg.strip_prefix("-agentpath:") {
+            (InjectionKind::NativeAgent, strip_agent_options(rest))
+        } else if let Some(rest) = arg.strip_prefix("--tweakClass=") {
+            (InjectionKind::TweakClass, rest.to_string())
+        } else if arg == "--tweakClass" {
+            match iter.next() {
+                Some(class) => (InjectionKind::TweakClass, class.clone()),
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+        if value.is_empty() {
+            continue;
+        }
+
+        let resolved = match kind {
+            InjectionKind::TweakClass => {
+                if KNOWN_TWEAKERS.contains(&value.as_str()) {
+                    continue;
+                }
+                base.and_then(|dir| find_class_jar(dir, &value))
+            }
+            _ => resolve_path(&value, base),
+        };
+        references.push(LaunchReference {
+            kind,
+            value,
+            source: source.to_string(),
+            resolved,
+        });
+    }
+    references
+}
+
+// Разбор командной строки: пробелы разделяют аргументы, двойные кавычки их объединяют.
+// Апостроф - обычный символ, как у Windows и JVM: C:\Users\O'Brien\agent.jar
+pub fn split_args(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_token = false;
+    for c in text.chars() {
+        match (quoted, c) {
+            (_, '"') => {
+                quoted = !quoted;
+                has_token = true;
+            }
+            (true, c) => current.push(c),
+            (false, c) if c.is_whitespace() => {
+                if has_token || !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            (false, c) => current.push(c),
+        }
+    }
+    if has_token || !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+// Опции агента идут после "=": -javaagent:agent.jar=debug. "=" бывает и в именах папок,
+// поэтому опции начинаются с первого "=" сразу после имени библиотеки агента
+fn strip_agent_options(value: &str) -> String {
+    let lower = value.to_ascii_lowercase();
+    let end = value.match_indices('=')
+        .map(|(pos, _)| pos)
+        .find(|&pos| AGENT_SUFFIXES.iter().any(|suffix| lower[..pos].ends_with(suffix)));
+    value[..end.unwrap_or(value.len())].to_string()
+}
+
+// Относительный путь JVM ищет от рабочей папки - у лаунчеров это папка игры
+fn resolve_path(value: &str, base: Option<&Path>) -> Option<PathBuf> {
+    let path = PathBuf::from(value);
+    let path = match base {
+        Some(base) if path.is_relative() => base.join(path),
+        _ => path,
+    };
+    path.is_file().then_some(path)
+}
+
+// --tweakClass в minecraftArguments (старый формат) или arguments.game (новый)
+fn version_tweakers(game_dir: &Path) -> Vec<LaunchReference> {
+    let mut references = Vec::new();
+    let Ok(versions) = std::fs::read_dir(game_dir.join("versions")) else {
+        return references;
+    };
+    for dir in versions.filter_map(Result::ok).map(|e| e.path()) {
+        let Some(name) = dir.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let json_path = dir.join(format!("{}.json", name));
+        let Some(version) = std::fs::read_to_string(&json_path).ok()
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok()) else {
+            continue;
+        };
+
+        let mut args: Vec<String> = version.get("minecraftArguments")
+            .and_then(Value::as_str)
+            .map(split_args)
+            .unwrap_or_default();
+        if let Some(game) = version.pointer("/arguments/game").and_then(Value::as_array) {
+            args.extend(game.iter().filter_map(Value::as_str).map(str::to_string));
+        }
+        let source = format!("versions/{}/{}.json", name, name);
+        references.extend(parse_args(&args, &source, Some(game_dir)));
+    }
+    references
+}
+
+// Первый jar в папке игры, где есть класс твикера
+fn find_class_jar(game_dir: &Path, class: &str) -> Option<PathBuf> {
+    let entry = format!("{}.class", class.replace('.', "/"));
+    TWEAKER_SEARCH_DIRS.iter()
+        .flat_map(|dir| WalkDir::new(game_dir.join(dir)).into_iter().filter_map(Result::ok))
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .filter(|p| p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")))
+        .find(|jar| {
+            File::open(jar).ok()
+                .and_then(|file| ZipArchive::new(file).ok())
+                .is_some_and(|mut archive| archive.by_name(&entry).is_ok())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::jar;
+
+    #[test]
+    fn splits_on_whitespace_and_double_quotes() {
+        assert_eq!(split_args(r#"-Xmx2G  "-javaagent:C:\Program Files\a.jar" -javaagent:C:\Users\O'Brien\b.jar """#), [
+            "-Xmx2G",
+            r"-javaagent:C:\Program Files\a.jar",
+            r"-javaagent:C:\Users\O'Brien\b.jar",
+            "",
+        ]);
+    }
+
+    #[test]
+    fn agent_options_are_stripped_after_library_name() {
+        let references = parse_args(&split_args(r"-javaagent:C:\a=b\agent.jar=debug,port=1 -agentpath:/opt/lib.SO=x -javaagent:tool.jar"),
+            "JAVA_TOOL_OPTIONS", None);
+        let values: Vec<(InjectionKind, &str)> = references.iter().map(|r| (r.kind, r.value.as_str())).collect();
+        assert_eq!(values, [
+            (InjectionKind::JavaAgent, r"C:\a=b\agent.jar"),
+            (InjectionKind::NativeAgent, "/opt/lib.SO"),
+            (InjectionKind::JavaAgent, "tool.jar"),
+        ]);
+        assert!(references.iter().all(|r| r.resolved.is_none() && r.source == "JAVA_TOOL_OPTIONS"));
+    }
+
+    #[test]
+    fn relative_agent_resolves_against_game_dir() {
+        let dir = std::env::temp_dir().join(format!("sbcheats-{}-agent-game", std::process::id()));
+        std::fs::create_dir_all(dir.join("agents")).unwrap();
+        std::fs::write(dir.join("agents/agent.jar"), b"agent").unwrap();
+        let references = parse_args(&split_args("-javaagent:agents/agent.jar=x -javaagent:agents/missing.jar"), "профиль", Some(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].resolved.as_deref(), Some(dir.join("agents/agent.jar").as_path()));
+        assert_eq!(references[1].resolved, None);
+    }
+
+    #[test]
+    fn only_unknown_tweakers_are_reported() {
+        let dir = std::env::temp_dir().join(format!("sbcheats-{}-tweaker-game", std::process::id()));
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::write(dir.join("mods/cheat.jar"), jar(&[("me/cheat/Tweaker.class", b"\xCA\xFE\xBA\xBE")])).unwrap();
+        let references = parse_args(&split_args("--tweakClass optifine.OptiFineTweaker --tweakClass=me.cheat.Tweaker \
+            --tweakClass net.minecraftforge.fml.common.launcher.FMLTweaker --tweakClass other.Missing"), "versions", Some(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let values: Vec<&str> = references.iter().map(|r| r.value.as_str()).collect();
+        assert_eq!(values, ["me.cheat.Tweaker", "other.Missing"]);
+        assert_eq!(references[0].resolved.as_deref(), Some(dir.join("mods/cheat.jar").as_path()));
+        assert_eq!(references[1].resolved, None);
+    }
+}
@@ -0,0 +1,166 @@
+// integrity.rs - Целостность jar игры и библиотек: json версии хранит SHA-1 клиента
+// и каждой библиотеки, пересчитываем офлайн. Классы, дописанные в ванильный jar,
+// сканер папки mods не увидит
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+// Пакеты самой игры
+const GAME_ROOTS: &[&str] = &["net/minecraft/", "com/mojang/"];
+// Обфускатор кладёт классы игры в корень под именами a..zzz (и a$b, a$1 для вложенных)
+const OBFUSCATED_NAME_MAX: usize = 3;
+// Баллы за несовпадение хеша и за чужие классы в jar клиента
+const MODIFIED_SCORE: u32 = 60;
+const FOREIGN_CLASSES_SCORE: u32 = 40;
+// Столько чужих классов выводится в отчёт поимённо
+const SHOWN_CLASSES: usize = 20;
+
+// Ожидаемый хеш из json версии
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedHash {
+    pub sha1: String,
+    // "versions/1.20.1/1.20.1.json: client" или координаты библиотеки
+    pub source: String,
+    // Jar клиента: у него проверяются пакеты классов
+    pub client: bool,
+}
+
+// Библиотека из json версии, скачанная в libraries/
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryFile {
+    pub path: PathBuf,
+    pub expected: ExpectedHash,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Modification {
+    pub details: Vec<String>,
+    pub score: u32,
+}
+
+// None - хеш совпал
+pub fn inspect(expected: &ExpectedHash, actual_sha1: &str, entries: &[String]) -> Option<Modification> {
+    if expected.sha1.eq_ignore_ascii_case(actual_sha1) {
+        return None;
+    }
+    let mut modification = Modification {
+        details: vec![format!("SHA-1 не совпадает с {}: ожидался {}, получен {}",
+            expected.source, expected.sha1.to_ascii_lowercase(), actual_sha1)],
+        score: MODIFIED_SCORE,
+    };
+    if !expected.client {
+        return Some(modification);
+    }
+
+    let foreign = foreign_classes(entries);
+    if !foreign.is_empty() {
+        modification.score += FOREIGN_CLASSES_SCORE;
+        modification.details.push(format!("Классы вне net/minecraft и com/mojang: {}", foreign.len()));
+        modification.details.extend(foreign.iter().take(SHOWN_CLASSES).map(|class| format!("Чужой класс: {}", class)));
+        if foreign.len() > SHOWN_CLASSES {
+            modification.details.push(format!("... и ещё {}", foreign.len() - SHOWN_CLASSES));
+        }
+    }
+    Some(modification)
+}
+
+// Класс в корне jar с именем, которое мог выдать обфускатор игры
+fn is_obfuscated_root_class(entry: &str) -> bool {
+    let Some(stem) = entry.strip_suffix(".class") else { return false };
+    let mut parts = stem.split('$');
+    let outer = parts.next().unwrap_or_default();
+    let short_name = |part: &str| {
+        (1..=OBFUSCATED_NAME_MAX).contains(&part.len()) && part.bytes().all(|b| b.is_ascii_lowercase())
+    };
+    short_name(outer) && parts.all(|inner| short_name(inner) || (!inner.is_empty() && inner.bytes().all(|b| b.is_ascii_digit())))
+}
+
+// Классы, которых нет в ванильном jar. Списка классов версии офлайн нет,
+// поэтому своими считаются пакеты игры и обфусцированные имена в корне
+pub fn foreign_classes(entries: &[String]) -> Vec<String> {
+    let mut classes: Vec<String> = entries.iter()
+        .filter(|entry| entry.ends_with(".class"))
+        .filter(|entry| !entry.starts_with("meta-inf/"))
+        .filter(|entry| entry.contains('/') || !is_obfuscated_root_class(entry))
+        .filter(|entry| !GAME_ROOTS.iter().any(|root| entry.starts_with(root)))
+        .cloned()
+        .collect();
+    classes.sort();
+    classes.dedup();
+    classes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected(client: bool) -> ExpectedHash {
+        ExpectedHash {
+            sha1: "ABCDEF".to_string(),
+            source: if client { "versions/1.20.1/1.20.1.json: client" } else { "org.ow2.asm:asm:9.6" }.to_string(),
+            client,
+        }
+    }
+
+    fn entries(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn matching_hash_is_not_modified() {
+        assert!(inspect(&expected(true), "abcdef", &entries(&["cheat/Hack.class"])).is_none());
+    }
+
+    #[test]
+    fn library_mismatch_scores_hash_only() {
+        let modification = inspect(&expected(false), "012345", &entries(&["cheat/Hack.class"])).unwrap();
+        assert_eq!(modification.score, MODIFIED_SCORE);
+        assert_eq!(modification.details, ["SHA-1 не совпадает с org.ow2.asm:asm:9.6: ожидался abcdef, получен 012345"]);
+    }
+
+    #[test]
+    fn client_lists_foreign_classes() {
+        let modification = inspect(&expected(true), "012345", &entries(&[
+            "net/minecraft/client/Minecraft.class",
+            "cheat/Hack.class",
+            "a.class",
+        ])).unwrap();
+        assert_eq!(modification.score, MODIFIED_SCORE + FOREIGN_CLASSES_SCORE);
+        assert_eq!(modification.details[1..], ["Классы вне net/minecraft и com/mojang: 1", "Чужой класс: cheat/Hack.class"]);
+    }
+
+    #[test]
+    fn long_class_list_is_cut() {
+        let names: Vec<String> = (0..SHOWN_CLASSES + 5).map(|i| format!("cheat/C{:02}.class", i)).collect();
+        let modification = inspect(&expected(true), "012345", &names).unwrap();
+        assert_eq!(modification.details.last().unwrap(), "... и ещё 5");
+        assert_eq!(modification.details.len(), 2 + SHOWN_CLASSES + 1);
+    }
+
+    #[test]
+    fn game_packages_are_not_foreign() {
+        let foreign = foreign_classes(&entries(&[
+            "com/mojang/blaze3d/Window.class",
+            "net/minecraft/server/Main.class",
+            "meta-inf/versions/9/module-info.class",
+            "ab.class",
+            "log_configs/client.xml",
+            "me/cheat/B.class",
+            "me/cheat/A.class",
+            "me/cheat/A.class",
+        ]));
+        assert_eq!(foreign, ["me/cheat/A.class", "me/cheat/B.class"]);
+    }
+
+    #[test]
+    fn root_level_injected_classes_are_foreign() {
+        let foreign = foreign_classes(&entries(&[
+            "a.class",
+            "bzz$a.class",
+            "cft$1.class",
+            "killaura.class",
+            "hack$a.class",
+            "ab$.class",
+        ]));
+        assert_eq!(foreign, ["ab$.class", "hack$a.class", "killaura.class"]);
+    }
+}
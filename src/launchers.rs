@@ -0,0 +1,527 @@
+// launchers.rs - Поиск лаунчеров и их инстансов: у каждого лаунчера свои папки с модами
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::injection::{split_args, JavaArgs};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Launcher {
+    Vanilla,
+    TLauncher,
+    Prism,
+    MultiMc,
+    CurseForge,
+    Modrinth,
+    AtLauncher,
+    GdLauncher,
+    Lunar,
+}
+
+impl Launcher {
+    pub fn label(self) -> &'static str {
+        match self {
+            Launcher::Vanilla => "Minecraft Launcher",
+            Launcher::TLauncher => "TLauncher",
+            Launcher::Prism => "Prism Launcher",
+            Launcher::MultiMc => "MultiMC",
+            Launcher::CurseForge => "CurseForge",
+            Launcher::Modrinth => "Modrinth App",
+            Launcher::AtLauncher => "ATLauncher",
+            Launcher::GdLauncher => "GDLauncher",
+            Launcher::Lunar => "Lunar Client",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    pub launcher: Launcher,
+    pub name: String,
+    // Папка игры: в ней mods/, versions/, logs/
+    pub game_dir: PathBuf,
+    pub minecraft_version: Option<String>,
+    pub loader: Option<String>,
+    // Аргументы JVM из настроек лаунчера: в них ищутся -javaagent и --tweakClass
+    pub java_args: Vec<JavaArgs>,
+}
+
+impl Instance {
+    fn new(launcher: Launcher, name: impl Into<String>, game_dir: PathBuf) -> Self {
+        Self {
+            launcher,
+            name: name.into(),
+            game_dir,
+            minecraft_version: None,
+            loader: None,
+            java_args: Vec::new(),
+        }
+    }
+
+    fn add_java_args(&mut self, source: String, args: Vec<String>) {
+        if !args.is_empty() {
+            self.java_args.push(JavaArgs { source, args });
+        }
+    }
+
+    pub fn mods_dir(&self) -> PathBuf {
+        self.game_dir.join("mods")
+    }
+
+    // "Prism Launcher - Fabric 1.20 (1.20.1, fabric)"
+    pub fn title(&self) -> String {
+        let mut title = format!("{} - {}", self.launcher.label(), self.name);
+        let extra: Vec<&str> = [&self.minecraft_version, &self.loader]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if !extra.is_empty() {
+            title.push_str(&format!(" ({})", extra.join(", ")));
+        }
+        title
+    }
+}
+
+// Где искать: домашняя папка, AppData/Roaming (~/.local/share, ~/Library/Application Support), Документы
+#[derive(Debug, Clone)]
+pub struct LauncherRoots {
+    pub home: PathBuf,
+    pub data: PathBuf,
+    pub documents: Option<PathBuf>,
+}
+
+impl LauncherRoots {
+    pub fn from_env() -> Option<Self> {
+        let home = dirs::home_dir()?;
+        Some(Self {
+            data: dirs::data_dir().unwrap_or_else(|| home.clone()),
+            documents: dirs::document_dir(),
+            home,
+        })
+    }
+}
+
+pub fn discover() -> Vec<Instance> {
+    LauncherRoots::from_env()
+        .map(|roots| discover_in(&roots))
+        .unwrap_or_default()
+}
+
+// Инстансы всех известных лаунчеров; одна папка игры попадает в список один раз
+pub fn discover_in(roots: &LauncherRoots) -> Vec<Instance> {
+    let mut instances = Vec::new();
+    vanilla(roots, &mut instances);
+    for (launcher, dir, config) in [
+        (Launcher::Prism, "PrismLauncher", "prismlauncher.cfg"),
+        (Launcher::MultiMc, "MultiMC", "multimc.cfg"),
+        (Launcher::MultiMc, "multimc", "multimc.cfg"),
+    ] {
+        multimc_like(launcher, &roots.data.join(dir), config, &mut instances);
+    }
+    curseforge(roots, &mut instances);
+    modrinth(roots, &mut instances);
+    atlauncher(roots, &mut instances);
+    gdlauncher(roots, &mut instances);
+    lunar(roots, &mut instances);
+
+    let mut seen = HashSet::new();
+    instances.retain(|instance| {
+        let key = instance.game_dir.canonicalize().unwrap_or_else(|_| instance.game_dir.clone());
+        seen.insert(key)
+    });
+    instances
+}
+
+// .minecraft и профили launcher_profiles.json со своей gameDir. TLauncher живёт там же
+fn vanilla(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    let candidates = [
+        roots.data.join(".minecraft"),
+        roots.home.join(".minecraft"),
+        roots.data.join("minecraft"),
+    ];
+    for root in candidates.iter().filter(|p| p.is_dir()) {
+        let launcher = if root.join("TlauncherProfiles.json").is_file() || roots.data.join(".tlauncher").is_dir() {
+            Launcher::TLauncher
+        } else {
+            Launcher::Vanilla
+        };
+        let mut main = Instance::new(launcher, ".minecraft", root.clone());
+        let mut separate = Vec::new();
+
+        // Профили без своей gameDir запускаются из .minecraft
+        let profiles = read_json(&root.join("launcher_profiles.json"));
+        for (id, profile) in profiles.iter()
+            .filter_map(|p| p.get("profiles").and_then(Value::as_object))
+            .flatten() {
+            let name = json_str(profile, &["name"]).filter(|n| !n.is_empty()).unwrap_or(id.as_str());
+            let java_args = json_str(profile, &["javaArgs"]).map(split_args).unwrap_or_default();
+            let source = format!("launcher_profiles.json: {}", name);
+            match json_str(profile, &["gameDir"]) {
+                Some(game_dir) => {
+                    let mut instance = Instance::new(launcher, name, root.join(game_dir));
+                    instance.minecraft_version = json_str(profile, &["lastVersionId"]).map(str::to_string);
+                    instance.add_java_args(source, java_args);
+                    separate.push(instance);
+                }
+                None => main.add_java_args(source, java_args),
+            }
+        }
+        out.push(main);
+        out.extend(separate);
+    }
+}
+
+// Prism и MultiMC: instances/<имя>/instance.cfg + mmc-pack.json, игра в .minecraft или minecraft.
+// Папку инстансов можно перенести: InstanceDir в конфиге лаунчера
+fn multimc_like(launcher: Launcher, data_dir: &Path, config: &str, out: &mut Vec<Instance>) {
+    if !data_dir.is_dir() {
+        return;
+    }
+    let global = read_ini(&data_dir.join(config));
+    let instances_dir = ini_value(&global, "InstanceDir")
+        .map(|value| data_dir.join(value))
+        .unwrap_or_else(|| data_dir.join("instances"));
+    let global_args = ini_value(&global, "JvmArgs").map(split_args).unwrap_or_default();
+
+    for dir in subdirs(&instances_dir) {
+        let cfg = read_ini(&dir.join("instance.cfg"));
+        if cfg.is_empty() {
+            continue;
+        }
+        let name = ini_value(&cfg, "name")
+            .map(str::to_string)
+            .unwrap_or_else(|| dir_name(&dir));
+        let game_dir = [".minecraft", "minecraft"].iter()
+            .map(|d| dir.join(d))
+            .find(|p| p.is_dir())
+            .unwrap_or_else(|| dir.join(".minecraft"));
+
+        let mut instance = Instance::new(launcher, name, game_dir);
+        // Свои аргументы инстанса заменяют глобальные только при OverrideJavaArgs=true
+        if ini_value(&cfg, "OverrideJavaArgs") == Some("true") {
+            let args = ini_value(&cfg, "JvmArgs").map(split_args).unwrap_or_default();
+            instance.add_java_args(format!("{}/instance.cfg", dir_name(&dir)), args);
+        } else {
+            instance.add_java_args(config.to_string(), global_args.clone());
+        }
+        if let Some(components) = read_json(&dir.join("mmc-pack.json"))
+            .and_then(|pack| pack.get("components").and_then(Value::as_array).cloned()) {
+            for component in &components {
+                let version = json_str(component, &["version"]).map(str::to_string);
+                match json_str(component, &["uid"]) {
+                    Some("net.minecraft") => instance.minecraft_version = version,
+                    Some(uid) => {
+                        if let Some(loader) = loader_from_uid(uid) {
+                            instance.loader = Some(loader.to_string());
+                        }
+                    }
+                    None => {}
+                }
+            }
+        }
+        out.push(instance);
+    }
+}
+
+fn loader_from_uid(uid: &str) -> Option<&'static str> {
+    match uid {
+        "net.fabricmc.fabric-loader" => Some("fabric"),
+        "org.quiltmc.quilt-loader" => Some("quilt"),
+        "net.minecraftforge" => Some("forge"),
+        "net.neoforged" => Some("neoforge"),
+        "com.mumfrey.liteloader" => Some("liteloader"),
+        _ => None,
+    }
+}
+
+// CurseForge: Instances/<имя>/minecraftinstance.json, моды прямо в папке инстанса
+fn curseforge(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    let mut bases = vec![roots.home.join("curseforge/minecraft/Instances")];
+    if let Some(ref documents) = roots.documents {
+        bases.push(documents.join("curseforge/minecraft/Instances"));
+        bases.push(documents.join("Curse/Minecraft/Instances"));
+    }
+    for dir in bases.iter().flat_map(|base| subdirs(base)) {
+        let manifest = read_json(&dir.join("minecraftinstance.json"));
+        let mut instance = Instance::new(Launcher::CurseForge, dir_name(&dir), dir.clone());
+        if let Some(ref manifest) = manifest {
+            if let Some(name) = json_str(manifest, &["name"]) {
+                instance.name = name.to_string();
+            }
+            instance.minecraft_version = json_str(manifest, &["gameVersion"]).map(str::to_string);
+            // "fabric-0.15.11", "forge-47.2.0"
+            instance.loader = json_str(manifest, &["baseModLoader", "name"])
+                .map(|l| l.split('-').next().unwrap_or(l).to_string());
+            let args = json_str(manifest, &["javaArgsOverride"]).map(split_args).unwrap_or_default();
+            instance.add_java_args(format!("{}/minecraftinstance.json", dir_name(&dir)), args);
+        }
+        out.push(instance);
+    }
+}
+
+// Modrinth App: profiles/<имя>/, в старых версиях рядом profile.json с метаданными
+fn modrinth(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    for app in ["ModrinthApp", "com.modrinth.theseus"] {
+        for dir in subdirs(&roots.data.join(app).join("profiles")) {
+            let mut instance = Instance::new(Launcher::Modrinth, dir_name(&dir), dir.clone());
+            if let Some(profile) = read_json(&dir.join("profile.json")) {
+                if let Some(name) = json_str(&profile, &["metadata", "name"]) {
+                    instance.name = name.to_string();
+                }
+                instance.minecraft_version = json_str(&profile, &["metadata", "game_version"]).map(str::to_string);
+                instance.loader = json_str(&profile, &["metadata", "loader"]).map(str::to_string);
+                let args = profile.pointer("/java/extra_arguments")
+                    .and_then(Value::as_array)
+                    .map(|args| args.iter().filter_map(Value::as_str).map(str::to_string).collect())
+                    .unwrap_or_default();
+                instance.add_java_args(format!("{}/profile.json", dir_name(&dir)), args);
+            }
+            out.push(instance);
+        }
+    }
+}
+
+// ATLauncher: instances/<имя>/instance.json
+fn atlauncher(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    for app in ["ATLauncher", "atlauncher"] {
+        for dir in subdirs(&roots.data.join(app).join("instances")) {
+            let Some(config) = read_json(&dir.join("instance.json")) else {
+                continue;
+            };
+            let name = json_str(&config, &["launcher", "name"]).map(str::to_string).unwrap_or_else(|| dir_name(&dir));
+            let mut instance = Instance::new(Launcher::AtLauncher, name, dir.clone());
+            instance.minecraft_version = json_str(&config, &["id"]).map(str::to_string);
+            instance.loader = json_str(&config, &["launcher", "loaderVersion", "type"]).map(str::to_lowercase);
+            let args = json_str(&config, &["launcher", "javaArguments"]).map(split_args).unwrap_or_default();
+            instance.add_java_args(format!("{}/instance.json", dir_name(&dir)), args);
+            out.push(instance);
+        }
+    }
+}
+
+// GDLauncher: старый - instances/<имя>/config.json, Carbon - data/instances/<имя>/instance
+fn gdlauncher(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    for base in [roots.data.join("gdlauncher_next/instances"), roots.data.join("gdlauncher_carbon/data/instances")] {
+        for dir in subdirs(&base) {
+            let carbon = dir.join("instance");
+            let game_dir = if carbon.is_dir() { carbon } else { dir.clone() };
+            let mut instance = Instance::new(Launcher::GdLauncher, dir_name(&dir), game_dir);
+            if let Some(config) = read_json(&dir.join("config.json")) {
+                if let Some(name) = json_str(&config, &["name"]) {
+                    instance.name = name.to_string();
+                }
+                instance.minecraft_version = json_str(&config, &["loader", "mcVersion"]).map(str::to_string);
+                instance.loader = json_str(&config, &["loader", "loaderType"]).map(str::to_string);
+                let args = json_str(&config, &["javaArgs"]).map(split_args).unwrap_or_default();
+                instance.add_java_args(format!("{}/config.json", dir_name(&dir)), args);
+            }
+            out.push(instance);
+        }
+    }
+}
+
+// Lunar Client: ~/.lunarclient/profiles/<профиль>/<версия>/mods - инстанс на каждую версию
+fn lunar(roots: &LauncherRoots, out: &mut Vec<Instance>) {
+    for dir in subdirs(&roots.home.join(".lunarclient/profiles")) {
+        let name = read_json(&dir.join("profile.json"))
+            .and_then(|profile| json_str(&profile, &["name"]).map(str::to_string))
+            .unwrap_or_else(|| dir_name(&dir));
+        for version_dir in subdirs(&dir).into_iter().filter(|d| d.join("mods").is_dir()) {
+            let mut instance = Instance::new(Launcher::Lunar, name.clone(), version_dir.clone());
+            instance.minecraft_version = Some(dir_name(&version_dir));
+            out.push(instance);
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(text.trim_start_matches('\u{feff}')).ok()
+}
+
+// Значение по пути ключей: json_str(v, &["launcher", "name"])
+fn json_str<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .try_fold(value, |v, key| v.get(key))?
+        .as_str()
+}
+
+// instance.cfg и *.cfg лаунчеров: key=value, секции [General] игнорируем
+fn read_ini(path: &Path) -> Vec<(String, String)> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+fn ini_value<'a>(ini: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    ini.iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_home(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name))
+    }
+
+    fn write(path: PathBuf, text: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    // Найденные инстансы одного лаунчера; папка удаляется
+    fn discover_only(home: &Path, launcher: Launcher) -> Vec<Instance> {
+        let roots = LauncherRoots { data: home.join("data"), documents: None, home: home.to_path_buf() };
+        let found = discover_in(&roots).into_iter().filter(|i| i.launcher == launcher).collect();
+        std::fs::remove_dir_all(home).unwrap();
+        found
+    }
+
+    fn agents(instance: &Instance) -> Vec<(&str, &str)> {
+        instance.java_args.iter()
+            .flat_map(|a| a.args.iter().map(move |arg| (a.source.as_str(), arg.as_str())))
+            .collect()
+    }
+
+    #[test]
+    fn prism_instances_and_java_args() {
+        let home = temp_home("prism");
+        let data = home.join("data/PrismLauncher");
+        write(data.join("prismlauncher.cfg"), "[General]\nJvmArgs=-javaagent:global.jar\n");
+        write(data.join("instances/fabric/instance.cfg"),
+            "[General]\nname=Fabric 1.20\nOverrideJavaArgs=true\nJvmArgs=-javaagent:own.jar -Xmx4G\n");
+        write(data.join("instances/fabric/mmc-pack.json"), r#"{"components": [
+            {"uid": "net.minecraft", "version": "1.20.1"},
+            {"uid": "net.fabricmc.fabric-loader", "version": "0.15.11"}
+        ]}"#);
+        std::fs::create_dir_all(data.join("instances/fabric/.minecraft")).unwrap();
+        write(data.join("instances/plain/instance.cfg"), "name=Plain\n");
+        std::fs::create_dir_all(data.join("instances/plain/minecraft")).unwrap();
+        std::fs::create_dir_all(data.join("instances/not-an-instance")).unwrap();
+
+        let found = discover_only(&home, Launcher::Prism);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].title(), "Prism Launcher - Fabric 1.20 (1.20.1, fabric)");
+        assert_eq!(found[0].game_dir, data.join("instances/fabric/.minecraft"));
+        assert_eq!(agents(&found[0]), [("fabric/instance.cfg", "-javaagent:own.jar"), ("fabric/instance.cfg", "-Xmx4G")]);
+        assert_eq!(found[1].game_dir, data.join("instances/plain/minecraft"));
+        assert_eq!(agents(&found[1]), [("prismlauncher.cfg", "-javaagent:global.jar")]);
+    }
+
+    #[test]
+    fn vanilla_profiles_with_own_game_dir() {
+        let home = temp_home("vanilla");
+        let root = home.join("data/.minecraft");
+        let separate = home.join("games/modded");
+        write(root.join("launcher_profiles.json"), &serde_json::json!({"profiles": {
+            "a": {"name": "Default", "javaArgs": "-Xmx2G -javaagent:agent.jar"},
+            "b": {"name": "Modded", "gameDir": separate, "lastVersionId": "fabric-loader-0.15.11-1.20.1"},
+        }}).to_string());
+
+        let found = discover_only(&home, Launcher::Vanilla);
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].name.as_str(), &found[0].game_dir), (".minecraft", &root));
+        assert_eq!(agents(&found[0]), [
+            ("launcher_profiles.json: Default", "-Xmx2G"),
+            ("launcher_profiles.json: Default", "-javaagent:agent.jar"),
+        ]);
+        assert_eq!((found[1].name.as_str(), &found[1].game_dir), ("Modded", &separate));
+        assert_eq!(found[1].minecraft_version.as_deref(), Some("fabric-loader-0.15.11-1.20.1"));
+    }
+
+    #[test]
+    fn curseforge_instance_manifest() {
+        let home = temp_home("curseforge");
+        let dir = home.join("curseforge/minecraft/Instances/pack");
+        write(dir.join("minecraftinstance.json"), r#"{"name": "All the Mods", "gameVersion": "1.20.1",
+            "baseModLoader": {"name": "forge-47.2.0"}, "javaArgsOverride": "-javaagent:cf.jar"}"#);
+
+        let found = discover_only(&home, Launcher::CurseForge);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title(), "CurseForge - All the Mods (1.20.1, forge)");
+        assert_eq!(found[0].mods_dir(), dir.join("mods"));
+        assert_eq!(agents(&found[0]), [("pack/minecraftinstance.json", "-javaagent:cf.jar")]);
+    }
+
+    #[test]
+    fn atlauncher_instance_json() {
+        let home = temp_home("atlauncher");
+        let dir = home.join("data/ATLauncher/instances/survival");
+        write(dir.join("instance.json"), r#"{"id": "1.19.2",
+            "launcher": {"name": "Survival", "loaderVersion": {"type": "Fabric"}, "javaArguments": "-javaagent:at.jar"}}"#);
+        // Без instance.json папка не инстанс
+        std::fs::create_dir_all(home.join("data/ATLauncher/instances/empty")).unwrap();
+
+        let found = discover_only(&home, Launcher::AtLauncher);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].title(), "ATLauncher - Survival (1.19.2, fabric)");
+        assert_eq!(found[0].game_dir, dir);
+        assert_eq!(agents(&found[0]), [("survival/instance.json", "-javaagent:at.jar")]);
+    }
+
+    #[test]
+    fn gdlauncher_old_and_carbon_layouts() {
+        let home = temp_home("gdlauncher");
+        let old = home.join("data/gdlauncher_next/instances/old");
+        write(old.join("config.json"), r#"{"name": "Old", "loader": {"mcVersion": "1.16.5", "loaderType": "forge"}}"#);
+        let carbon = home.join("data/gdlauncher_carbon/data/instances/new");
+        std::fs::create_dir_all(carbon.join("instance/mods")).unwrap();
+
+        let found = discover_only(&home, Launcher::GdLauncher);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].title(), "GDLauncher - Old (1.16.5, forge)");
+        assert_eq!(found[0].game_dir, old);
+        assert_eq!((found[1].name.as_str(), &found[1].game_dir), ("new", &carbon.join("instance")));
+    }
+
+    #[test]
+    fn lunar_instance_per_version() {
+        let home = std::env::temp_dir().join(format!("sbcheats-{}-lunar", std::process::id()));
+        let profile = home.join(".lunarclient/profiles/lunar");
+        for dir in ["1.8/mods", "1.20/mods", "1.20/logs", "cache"] {
+            std::fs::create_dir_all(profile.join(dir)).unwrap();
+        }
+        std::fs::write(profile.join("profile.json"), r#"{"name": "Default"}"#).unwrap();
+        let roots = LauncherRoots { data: home.join("data"), documents: None, home: home.clone() };
+
+        let mut found: Vec<(String, Option<String>, PathBuf)> = discover_in(&roots).into_iter()
+            .filter(|i| i.launcher == Launcher::Lunar)
+            .map(|i| (i.name.clone(), i.minecraft_version.clone(), i.mods_dir()))
+            .collect();
+        std::fs::remove_dir_all(&home).unwrap();
+        found.sort();
+
+        assert_eq!(found, [
+            ("Default".to_string(), Some("1.20".to_string()), profile.join("1.20/mods")),
+            ("Default".to_string(), Some("1.8".to_string()), profile.join("1.8/mods")),
+        ]);
+    }
+}
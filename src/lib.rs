@@ -0,0 +1,33 @@
+// lib.rs - Детектор и сканер без GUI: используется и окном, и CLI
+pub mod allowlist;
+pub mod challenge;
+pub mod classfile;
+pub mod detector;
+pub mod errors;
+pub mod hashes;
+pub mod injection;
+pub mod integrity;
+pub mod launchers;
+pub mod limits;
+pub mod logs;
+pub mod metadata;
+pub mod report;
+pub mod rules;
+pub mod scanner;
+pub mod signing;
+pub mod versions;
+
+mod mixin;
+mod obfuscation;
+mod sniff;
+#[cfg(test)]
+mod testutil;
+mod zipcheck;
+
+pub use allowlist::Allowlist;
+pub use detector::{CheatDetector, FileScan, FindingKind, JarRecord, ThreatResult};
+pub use errors::{ScanError, ScanErrorKind};
+pub use limits::ScanLimits;
+pub use report::{ReportFormat, ScanReport, Verdict};
+pub use scanner::{ScanMessage, ScanStats, ScanTarget, Scanner};
+pub use signing::{ReportSigner, TrustedKeys, Verification};
@@ -0,0 +1,42 @@
+// limits.rs - Ограничения на разбор архивов (защита от zip-бомб)
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+
+// Степень сжатия проверяется только у записей не меньше этого размера:
+// маленький текст из одних пробелов сжимается сильно, но безвреден
+pub const RATIO_MIN_SIZE: u64 = 1024 * 1024;
+
+// В файле базы: секция [limits], любое поле можно опустить
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanLimits {
+    // Записей в одном архиве
+    pub max_entries: usize,
+    // Распакованный размер одного архива (заявленный) и всех вложенных архивов
+    // файла вместе (фактический)
+    pub max_uncompressed_size: u64,
+    // Распакованный размер / сжатый для одной записи
+    pub max_compression_ratio: u64,
+    // Уровней jar-in-jar
+    pub max_nesting_depth: usize,
+    // Время на файл на диске вместе со всеми вложенными архивами
+    pub time_budget_secs: u64,
+}
+
+impl Default for ScanLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 65_535,
+            max_uncompressed_size: 512 * 1024 * 1024,
+            max_compression_ratio: 100,
+            max_nesting_depth: 3,
+            time_budget_secs: 30,
+        }
+    }
+}
+
+impl ScanLimits {
+    pub fn deadline(&self) -> Instant {
+        Instant::now() + Duration::from_secs(self.time_budget_secs)
+    }
+}
@@ -0,0 +1,315 @@
+// logs.rs - Следы читов в логах и краш-репортах: jar можно удалить перед проверкой,
+// но список модов и стектрейсы остаются в logs/ и crash-reports/
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+use flate2::read::GzDecoder;
+use regex::Regex;
+use crate::metadata::ModMetadata;
+use crate::report::format_timestamp;
+
+// Строка длиннее обрезается в отчёте
+const MAX_LINE_SHOWN: usize = 200;
+
+// Что удалось вытащить из одной строки лога
+#[derive(Debug, Clone, Default)]
+pub struct LogEvidence {
+    // С единицы
+    pub line: usize,
+    pub text: String,
+    // Время из префикса строки: "[12:34:56]"
+    pub time: Option<String>,
+    // id и версия из списка модов Fabric / Forge
+    pub mods: Vec<ModMetadata>,
+    pub jars: Vec<String>,
+    // Пути записей, как в архиве: "net/java/i.class"
+    pub classes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogFile {
+    pub path: PathBuf,
+    // Дата из имени файла, иначе дата изменения
+    pub date: Option<String>,
+    pub evidence: Vec<LogEvidence>,
+}
+
+impl LogEvidence {
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty() && self.jars.is_empty() && self.classes.is_empty()
+    }
+
+    // "2024-05-01 [12:34:56]"
+    pub fn when(&self, date: Option<&str>) -> String {
+        match (date, self.time.as_deref()) {
+            (Some(date), Some(time)) => format!("{} [{}]", date, time),
+            (Some(date), None) => date.to_string(),
+            (None, Some(time)) => format!("[{}]", time),
+            (None, None) => "в неизвестную дату".to_string(),
+        }
+    }
+
+    pub fn shown_text(&self) -> String {
+        let text = self.text.trim();
+        match text.char_indices().nth(MAX_LINE_SHOWN) {
+            Some((end, _)) => format!("{}...", &text[..end]),
+            None => text.to_string(),
+        }
+    }
+}
+
+// logs/latest.log, logs/debug.log, logs/*.log.gz и crash-reports/*.txt папки игры
+pub fn collect(game_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for (dir, suffixes) in [("logs", &[".log", ".log.gz", ".txt"][..]), ("crash-reports", &[".txt"][..])] {
+        let Ok(entries) = std::fs::read_dir(game_dir.join(dir)) else {
+            continue;
+        };
+        files.extend(entries.filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .filter(|p| {
+                let name = p.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+                suffixes.iter().any(|suffix| name.ends_with(suffix))
+            }));
+    }
+    files.sort();
+    files
+}
+
+// Читается не больше max_size байт (после распаковки): лог может быть огромным
+pub fn read(path: &Path, max_size: u64) -> Result<LogFile, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let is_gzip = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"));
+    let reader: Box<dyn Read> = if is_gzip { Box::new(GzDecoder::new(file)) } else { Box::new(file) };
+    let mut reader = BufReader::new(reader.take(max_size));
+
+    let mut evidence = Vec::new();
+    let mut buffer = Vec::new();
+    let mut number = 0;
+    // Строки списка модов без префикса времени - берём время последней строки с ним
+    let mut last_time = None;
+    loop {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        number += 1;
+        // Логи Windows бывают в cp1251 - нужные нам имена всё равно ASCII
+        let line = String::from_utf8_lossy(&buffer);
+        let mut found = parse_line(&line, number);
+        match found.time {
+            Some(ref time) => last_time = Some(time.clone()),
+            None => found.time = last_time.clone(),
+        }
+        if !found.is_empty() {
+            evidence.push(found);
+        }
+    }
+
+    Ok(LogFile {
+        path: path.to_path_buf(),
+        date: file_date(path),
+        evidence,
+    })
+}
+
+pub fn parse_line(line: &str, number: usize) -> LogEvidence {
+    let patterns = patterns();
+    let line = line.trim_end();
+    let mut evidence = LogEvidence {
+        line: number,
+        text: line.to_string(),
+        time: patterns.time.captures(line).map(|c| c[1].to_string()),
+        ..LogEvidence::default()
+    };
+
+    // Fabric: "\t- freecam 1.2.0", вложенные "\t   \--- sub 1.0"
+    if let Some(c) = patterns.fabric_mod.captures(line) {
+        evidence.mods.push(mod_entry("fabric", &c[1], Some(&c[2])));
+    }
+    // Краш-репорт Fabric: "\t\tfreecam: Freecam 1.2.0"
+    if let Some(c) = patterns.fabric_crash_mod.captures(line) {
+        evidence.mods.push(mod_entry("fabric", &c[1], c.get(2).map(|m| m.as_str())));
+    }
+    // Краш-репорт Forge: "freecam-1.2.jar |Freecam |freecam |1.2 |DONE |Manifest: ..."
+    let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+    if cells.len() >= 4 && cells[0].to_ascii_lowercase().ends_with(".jar") && patterns.mod_id.is_match(cells[2]) {
+        evidence.mods.push(mod_entry("forge", cells[2], Some(cells[3])));
+    }
+
+    for m in patterns.jar.find_iter(line) {
+        let name = m.as_str().rsplit(['/', '\\']).next().unwrap_or(m.as_str());
+        if !evidence.jars.iter().any(|j| j == name) {
+            evidence.jars.push(name.to_string());
+        }
+    }
+
+    // Классы - только из строк, где они точно загружены: кадры стектрейса
+    // ("at net.java.i.a(i.java:10)") и применённые Mixin. Свободный текст
+    // (чат, пути, настройки) дал бы совпадения по чужим словам
+    let frames = patterns.frame.captures_iter(line).map(|c| c.get(1).unwrap());
+    let mixin_line = line.to_ascii_lowercase().contains("mixin");
+    let mixins = patterns.mixin.captures_iter(line).filter(|_| mixin_line).map(|c| c.get(1).unwrap());
+    for m in frames.chain(mixins) {
+        // Вложенные классы лежат в своих файлах: "a/B$C.class"
+        let entry = format!("{}.class", m.as_str().replace('.', "/")).to_lowercase();
+        if !evidence.classes.contains(&entry) {
+            evidence.classes.push(entry);
+        }
+    }
+    evidence
+}
+
+struct Patterns {
+    time: Regex,
+    fabric_mod: Regex,
+    fabric_crash_mod: Regex,
+    mod_id: Regex,
+    jar: Regex,
+    frame: Regex,
+    mixin: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        time: Regex::new(r"^\[(?:[^\]]*\s)?(\d{1,2}:\d{2}:\d{2})(?:\.\d+)?\]").unwrap(),
+        fabric_mod: Regex::new(r"^\s+(?:-|[\\|]-+)\s+([a-z][a-z0-9_\-]{1,63})\s+(\S+)$").unwrap(),
+        fabric_crash_mod: Regex::new(r"^\t\t([a-z][a-z0-9_\-]{1,63}): .*?(\S+)?$").unwrap(),
+        mod_id: Regex::new(r"^[a-z][a-z0-9_\-]{1,63}$").unwrap(),
+        jar: Regex::new(r"[\w.+\-\[\]()]+\.jar\b").unwrap(),
+        // "at net.java.i.a(", Forge: "at TRANSFORMER/cheat@1.0/net.java.i.a("
+        frame: Regex::new(r"^\s*at\s+(?:\S+/)?([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)+)\.[\w$<>]+\(").unwrap(),
+        // "cheat.mixins.json:MixinMinecraft", "Mixing client.MixinMinecraft from mixins.cheat.json"
+        mixin: Regex::new(r"(?:[\w\-.]+\.json:|\bMixing\s+)([A-Za-z_$][\w$]*(?:\.[A-Za-z_$][\w$]*)*)").unwrap(),
+    })
+}
+
+fn mod_entry(loader: &str, id: &str, version: Option<&str>) -> ModMetadata {
+    ModMetadata {
+        loader: loader.to_string(),
+        id: id.to_string(),
+        version: version.map(str::to_string),
+        ..ModMetadata::default()
+    }
+}
+
+// "2024-05-01-1.log.gz", "crash-2024-05-01_12.34.56-client.txt"; у latest.log - дата изменения
+fn file_date(path: &Path) -> Option<String> {
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let date = DATE.get_or_init(|| Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap());
+    let name = path.file_name()?.to_string_lossy();
+    if let Some(m) = date.find(&name) {
+        return Some(m.as_str().to_string());
+    }
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let secs = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(format_timestamp(secs).chars().take(10).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn classes_come_from_stack_frames() {
+        let evidence = parse_line("\tat net.java.i.a(i.java:10) ~[?:?]", 7);
+        assert_eq!(evidence.line, 7);
+        assert_eq!(evidence.classes, ["net/java/i.class"]);
+
+        let forge = parse_line("\tat TRANSFORMER/cheat@1.0/net.java.Hit$Box.lambda$run$0(Hit.java:5) ~[cheat.jar:?]", 1);
+        assert_eq!(forge.classes, ["net/java/hit$box.class"]);
+    }
+
+    #[test]
+    fn classes_come_from_mixin_lines() {
+        let evidence = parse_line("[12:34:56] [main/WARN]: Mixin apply for mod cheat failed cheat.mixins.json:client.MixinMinecraft \
+            from mod cheat -> net.minecraft.class_310", 1);
+        assert_eq!(evidence.time.as_deref(), Some("12:34:56"));
+        assert_eq!(evidence.classes, ["client/mixinminecraft.class"]);
+    }
+
+    #[test]
+    fn free_text_is_not_a_class() {
+        for line in [
+            "[12:00:00] [Render thread/INFO]: [CHAT] <player> look at net.java.i lol",
+            "[12:00:00] [main/INFO]: Loading config from config/net.java.i.json:12",
+            "[12:00:00] [main/INFO]: Launching target 'client' with arguments [--version, fabric.loader.1.20]",
+        ] {
+            assert!(parse_line(line, 1).classes.is_empty(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn reads_fabric_and_forge_mod_lists() {
+        let fabric = parse_line("\t- freecam 1.2.0", 1);
+        assert_eq!((fabric.mods[0].id.as_str(), fabric.mods[0].version.as_deref()), ("freecam", Some("1.2.0")));
+        assert!(fabric.classes.is_empty());
+
+        let forge = parse_line("\tFreecam-1.2.jar |Freecam |freecam |1.2 |DONE |Manifest: NOSIGNATURE", 1);
+        assert_eq!(forge.mods[0].loader, "forge");
+        assert_eq!(forge.jars, ["Freecam-1.2.jar"]);
+    }
+
+    #[test]
+    fn reads_gzip_log_and_carries_time() {
+        let dir = temp_dir("gzip-log");
+        let path = dir.join("2024-05-01-1.log.gz");
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"[12:34:56] [main/INFO]: Loading 2 mods:\n\t- freecam 1.2.0\n\t- minecraft 1.20.1\n").unwrap();
+        std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+        let log = read(&path, 1 << 20).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(log.date.as_deref(), Some("2024-05-01"));
+        let lines: Vec<(usize, Option<&str>, &str)> = log.evidence.iter()
+            .map(|e| (e.line, e.time.as_deref(), e.mods[0].id.as_str()))
+            .collect();
+        assert_eq!(lines, [(2, Some("12:34:56"), "freecam"), (3, Some("12:34:56"), "minecraft")]);
+    }
+
+    #[test]
+    fn read_stops_at_size_limit() {
+        let dir = temp_dir("limited-log");
+        let path = dir.join("latest.log");
+        std::fs::write(&path, "\t- freecam 1.2.0\n\t- xray 1.0.0\n").unwrap();
+        let log = read(&path, 18).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(log.evidence.len(), 1);
+        assert_eq!(log.evidence[0].mods[0].id, "freecam");
+    }
+
+    #[test]
+    fn collects_logs_and_crash_reports() {
+        let dir = temp_dir("collect-logs");
+        for name in ["logs/latest.log", "logs/2024-05-01-1.log.gz", "logs/telemetry.json",
+            "crash-reports/crash-2024-05-01_12.34.56-client.txt", "crash-reports/old.log"] {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let files = collect(&dir);
+        let names: Vec<String> = files.iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(names, ["crash-reports/crash-2024-05-01_12.34.56-client.txt",
+            "logs/2024-05-01-1.log.gz", "logs/latest.log"]);
+    }
+}
@@ -1,133 +1,107 @@
 // main.rs
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod detector;
-
-
-use detector::{CheatDetector, ThreatResult};
 use eframe::egui;
-use rayon::prelude::*;
-use std::path::{Path, PathBuf};
+use sbcheats_scanner::launchers::{self, Instance};
+use sbcheats_scanner::{
+    signing, Allowlist, CheatDetector, JarRecord, ReportFormat, ReportSigner, ScanError, ScanMessage, ScanReport,
+    ScanStats, Scanner, ThreatResult, TrustedKeys, Verification,
+};
+use std::path::PathBuf;
 use std::sync::mpsc;
-use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}};
 use std::thread;
-use std::time::Instant;
-use walkdir::WalkDir;
-
-// ==================== СТРУКТУРЫ ====================
-
-
-#[derive(Debug, Clone)]
-enum ScanMessage {
-    Progress(f32),
-    ThreatFound(ThreatResult),
-    Stats(ScanStats),
-    Complete,
-}
-
-#[derive(Debug, Clone)]
-struct ScanStats {
-    total: usize,
-    checked: usize,
-    found: usize,
-}
-
-// ==================== СКАНЕР ====================
-
-fn find_jar_files(path: &Path) -> Vec<PathBuf> {
-    WalkDir::new(path)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .par_bridge()
-        .filter_map(|entry| {
-            let path = entry.path();
-            if !path.extension()?.to_str()?.eq_ignore_ascii_case("jar") {
-                return None;
-            }
-            let size = std::fs::metadata(path).ok()?.len();
-            if size >= 1024 && size <= 500 * 1024 * 1024 {
-                Some(path.to_path_buf())
-            } else {
-                None
-            }
-        })
-        .collect()
-}
-
-fn scan_files(
-    detector: CheatDetector,
-    files: Vec<PathBuf>,
-    sender: mpsc::Sender<ScanMessage>,
-    cancel: Arc<AtomicBool>,
-    num_threads: usize,
-) {
-    let total = files.len();
-    let checked = Arc::new(AtomicUsize::new(0));
-    let found = Arc::new(AtomicUsize::new(0));
-
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_threads)
-        .build_global()
-        .ok();
-
-    files.par_iter().for_each(|jar_path| {
-        if cancel.load(Ordering::Relaxed) {
-            return;
-        }
-
-        if let Some(threat) = detector.check_jar_file(jar_path) {
-            found.fetch_add(1, Ordering::Relaxed);
-            sender.send(ScanMessage::ThreatFound(threat)).ok();
-        }
-
-        let current = checked.fetch_add(1, Ordering::Relaxed) + 1;
-
-        // Обновление каждые 50 файлов
-        if current % 50 == 0 || current == total {
-            sender.send(ScanMessage::Progress(current as f32 / total as f32)).ok();
-            sender.send(ScanMessage::Stats(ScanStats {
-                total,
-                checked: current,
-                found: found.load(Ordering::Relaxed),
-            })).ok();
-        }
-    });
-
-    sender.send(ScanMessage::Complete).ok();
-}
+use std::time::{Duration, Instant, SystemTime};
 
 // ==================== GUI ====================
 
 struct CheatDetectorApp {
     search_path: String,
+    // Вместо пути - папки mods всех найденных инстансов лаунчеров
+    scan_all_instances: bool,
+    // Аргументы запуска, версии и логи инстансов, к которым относится путь (см. Scanner);
+    // при "Все инстансы" включено всегда
+    launch_analysis: bool,
+    instances: Vec<Instance>,
+    database_path: String,
+    detector: CheatDetector,
+    database_status: String,
+    database_error: Option<String>,
+    // Проверка не запущена: например, при "Все инстансы" не нашлось ни одной папки mods
+    scan_error: Option<String>,
     scanning: bool,
     stats: ScanStats,
     threats: Vec<ThreatResult>,
+    suspicious: Vec<ThreatResult>,
+    suppressed: Vec<ThreatResult>,
+    errors: Vec<ScanError>,
+    // Проверенные jar с модами, и чистые тоже
+    jars: Vec<JarRecord>,
     scan_start: Option<Instant>,
+    // Для отчёта: что и когда проверяли
+    scanned_paths: Vec<PathBuf>,
+    // Фраза модератора: вводится до проверки, из неё и результатов - код проверки
+    challenge: String,
+    scan_challenge: Option<String>,
+    // Считается один раз по завершении проверки
+    verification_code: Option<String>,
+    scan_started_at: SystemTime,
+    scan_duration: Option<Duration>,
+    // Результат последнего экспорта: Ok(путь) или текст ошибки
+    export_status: Option<Result<String, String>>,
+    // Ключ подписи отчётов и доверенные ключи для проверки чужих отчётов
+    signer: Option<ReportSigner>,
+    trusted_keys: TrustedKeys,
+    verification: Option<Result<(ScanReport, Verification), String>>,
     num_threads: usize,
     progress: f32,
     receiver: Option<mpsc::Receiver<ScanMessage>>,
-    cancel_flag: Option<Arc<AtomicBool>>,
+    // Клон сканера из рабочего потока - для отмены
+    scanner: Option<Scanner>,
 }
 
 impl Default for CheatDetectorApp {
     fn default() -> Self {
-        Self {
+        let mut app = Self {
             search_path: dirs::home_dir()
                 .unwrap_or_default()
                 .join(".minecraft/mods")
                 .display()
                 .to_string(),
+            scan_all_instances: false,
+            launch_analysis: false,
+            instances: Vec::new(),
+            database_path: CheatDetector::default_database_path()
+                .display()
+                .to_string(),
+            detector: CheatDetector::new(),
+            database_status: String::new(),
+            database_error: None,
+            scan_error: None,
             scanning: false,
-            stats: ScanStats { total: 0, checked: 0, found: 0 },
+            stats: ScanStats::default(),
             threats: Vec::new(),
+            suspicious: Vec::new(),
+            suppressed: Vec::new(),
+            errors: Vec::new(),
+            jars: Vec::new(),
             scan_start: None,
+            scanned_paths: Vec::new(),
+            challenge: String::new(),
+            scan_challenge: None,
+            verification_code: None,
+            scan_started_at: SystemTime::now(),
+            scan_duration: None,
+            export_status: None,
+            signer: None,
+            trusted_keys: TrustedKeys::default(),
+            verification: None,
             num_threads: num_cpus::get().clamp(2, 8),
             progress: 0.0,
             receiver: None,
-            cancel_flag: None,
-        }
+            scanner: None,
+        };
+        app.reload_database();
+        app
     }
 }
 
@@ -138,11 +112,28 @@ impl eframe::App for CheatDetectorApp {
             while let Ok(msg) = receiver.try_recv() {
                 match msg {
                     ScanMessage::Progress(p) => self.progress = p,
-                    ScanMessage::ThreatFound(t) => self.threats.push(t),
+                    ScanMessage::ThreatFound(t) => {
+                        if t.suppressed.is_some() {
+                            self.suppressed.push(*t);
+                        } else if t.is_heuristic() {
+                            self.suspicious.push(*t);
+                        } else {
+                            self.threats.push(*t);
+                        }
+                    }
                     ScanMessage::Stats(s) => self.stats = s,
+                    ScanMessage::Error(e) => self.errors.push(e),
+                    ScanMessage::JarChecked(jar) => self.jars.push(*jar),
+                    ScanMessage::ExtraPath(path) => {
+                        if !self.scanned_paths.contains(&path) {
+                            self.scanned_paths.push(path);
+                        }
+                    }
                     ScanMessage::Complete => {
                         self.scanning = false;
                         self.progress = 1.0;
+                        self.scan_duration = self.scan_start.map(|start| start.elapsed());
+                        self.verification_code = self.build_report().verification_code();
                     }
                 }
             }
@@ -169,7 +160,30 @@ impl eframe::App for CheatDetectorApp {
 
                 ui.horizontal(|ui| {
                     ui.label("Путь:");
-                    ui.text_edit_singleline(&mut self.search_path);
+                    ui.add_enabled(!self.scan_all_instances, egui::TextEdit::singleline(&mut self.search_path));
+                    if ui.add_enabled(!self.scanning, egui::Checkbox::new(&mut self.scan_all_instances, "Все инстансы"))
+                        .changed() && self.scan_all_instances {
+                        self.instances = launchers::discover();
+                    }
+                    ui.add_enabled(!self.scanning && !self.scan_all_instances,
+                        egui::Checkbox::new(&mut self.launch_analysis, "Запуск, версии и логи"));
+                });
+
+                if self.scan_all_instances {
+                    egui::CollapsingHeader::new(format!("Найдено инстансов: {}", self.instances.len()))
+                        .show(ui, |ui| {
+                            for instance in &self.instances {
+                                ui.label(instance.title());
+                                ui.label(egui::RichText::new(instance.game_dir.display().to_string())
+                                    .small().color(egui::Color32::GRAY));
+                            }
+                        });
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Фраза модератора:");
+                    ui.add_enabled(!self.scanning, egui::TextEdit::singleline(&mut self.challenge)
+                        .hint_text("необязательно"));
                 });
 
                 ui.add_space(8.0);
@@ -192,8 +206,8 @@ impl eframe::App for CheatDetectorApp {
                     }
 
                     if self.scanning && ui.button("❌ Отмена").clicked() {
-                        if let Some(ref flag) = self.cancel_flag {
-                            flag.store(true, Ordering::Relaxed);
+                        if let Some(ref scanner) = self.scanner {
+                            scanner.cancel();
                         }
                     }
 
@@ -201,6 +215,40 @@ impl eframe::App for CheatDetectorApp {
                     ui.add(egui::Slider::new(&mut self.num_threads, 1..=16));
                 });
 
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("База:");
+                    ui.text_edit_singleline(&mut self.database_path);
+
+                    if ui.button("📄 Файл").clicked() {
+                        if let Some(p) = rfd::FileDialog::new()
+                            .add_filter("Сигнатуры", &["json", "toml"])
+                            .pick_file() {
+                            self.database_path = p.display().to_string();
+                            self.reload_database();
+                        }
+                    }
+
+                    if ui.add_enabled(!self.scanning, egui::Button::new("🔄 Перезагрузить базу")).clicked() {
+                        self.reload_database();
+                    }
+
+                    if ui.button("🔏 Проверить отчёт").clicked() {
+                        self.verify_report();
+                    }
+                });
+
+                ui.label(egui::RichText::new(&self.database_status)
+                    .small().color(egui::Color32::GRAY));
+                if let Some(ref err) = self.database_error {
+                    ui.label(egui::RichText::new(format!("Ошибка базы: {}", err))
+                        .small().color(egui::Color32::from_rgb(248, 100, 73)));
+                }
+                if let Some(ref err) = self.scan_error {
+                    ui.label(egui::RichText::new(err).color(egui::Color32::from_rgb(248, 100, 73)));
+                }
+
                 if self.scanning {
                     ui.add_space(8.0);
                     ui.add(egui::ProgressBar::new(self.progress)
@@ -210,6 +258,27 @@ impl eframe::App for CheatDetectorApp {
 
             ui.add_space(10.0);
 
+            // Проверка присланного отчёта
+            if let Some(ref verification) = self.verification {
+                let mut close = false;
+                ui.group(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("🔏 Проверка отчёта").strong());
+                        close = ui.small_button("✖").clicked();
+                    });
+                    match verification {
+                        Ok((report, result)) => verification_summary(ui, report, result),
+                        Err(err) => {
+                            ui.label(egui::RichText::new(err).color(egui::Color32::from_rgb(248, 100, 73)));
+                        }
+                    }
+                });
+                if close {
+                    self.verification = None;
+                }
+                ui.add_space(10.0);
+            }
+
             // Статистика
             ui.group(|ui| {
                 ui.label(egui::RichText::new("📊 Статистика").strong());
@@ -220,14 +289,64 @@ impl eframe::App for CheatDetectorApp {
                     ui.separator();
                     ui.label(format!("✅ Проверено: {}", self.stats.checked));
                     ui.separator();
-                    let color = if self.stats.found > 0 {
+                    let color = if self.stats.found > 0 || !self.errors.is_empty() {
                         egui::Color32::from_rgb(248, 180, 73)
                     } else {
                         egui::Color32::GREEN
                     };
                     ui.label(egui::RichText::new(format!("⚠️ Найдено: {}", self.stats.found))
                         .color(color));
+                    if self.stats.suspicious > 0 {
+                        ui.separator();
+                        ui.label(egui::RichText::new(format!("🔎 Подозрительных: {}", self.stats.suspicious))
+                            .color(egui::Color32::from_rgb(248, 180, 73)));
+                    }
+                    if self.stats.suppressed > 0 {
+                        ui.separator();
+                        ui.label(format!("🔕 Подавлено: {}", self.stats.suppressed));
+                    }
+                    if !self.errors.is_empty() {
+                        ui.separator();
+                        ui.label(egui::RichText::new(format!("❗ Ошибок: {}", self.errors.len()))
+                            .color(egui::Color32::from_rgb(248, 100, 73)));
+                    }
                 });
+
+                // Итог: "чисто" только если проверено всё и без ошибок
+                if !self.scanning && self.scan_start.is_some() {
+                    let incomplete = !self.errors.is_empty() || self.stats.checked < self.stats.total;
+                    if incomplete {
+                        ui.label(egui::RichText::new(
+                            "⚠️ Проверка неполная: часть файлов не проверена, отсутствие угроз не гарантировано")
+                            .color(egui::Color32::from_rgb(248, 180, 73)));
+                    } else if self.threats.is_empty() && self.suspicious.is_empty() {
+                        ui.label(egui::RichText::new("✅ Угроз не найдено")
+                            .color(egui::Color32::GREEN));
+                    }
+
+                    if let Some(ref code) = self.verification_code {
+                        ui.label(egui::RichText::new(format!("🔑 Код проверки: {}", code))
+                            .size(20.0).strong().monospace());
+                    }
+
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("💾 Сохранить отчёт").clicked() {
+                            self.export_report();
+                        }
+                        match self.export_status {
+                            Some(Ok(ref path)) => {
+                                ui.label(egui::RichText::new(format!("Сохранено: {}", path))
+                                    .small().color(egui::Color32::GRAY));
+                            }
+                            Some(Err(ref err)) => {
+                                ui.label(egui::RichText::new(format!("Ошибка сохранения: {}", err))
+                                    .small().color(egui::Color32::from_rgb(248, 100, 73)));
+                            }
+                            None => {}
+                        }
+                    });
+                }
             });
 
             ui.add_space(10.0);
@@ -240,12 +359,61 @@ impl eframe::App for CheatDetectorApp {
 
                     egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
                         for threat in &self.threats {
+                            threat_card(ui, threat, egui::Color32::RED);
+                        }
+                    });
+                });
+            }
+
+            // Эвристики: неизвестные, но подозрительные jar
+            if !self.suspicious.is_empty() {
+                ui.add_space(10.0);
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new(format!("🔎 Подозрительные (проверить вручную): {}", self.suspicious.len()))
+                        .strong().color(egui::Color32::from_rgb(248, 180, 73)));
+
+                    egui::ScrollArea::vertical().id_source("suspicious").max_height(300.0).show(ui, |ui| {
+                        for threat in &self.suspicious {
+                            threat_card(ui, threat, egui::Color32::from_rgb(248, 180, 73));
+                        }
+                    });
+                });
+            }
+
+            // Файлы, которые не удалось проверить
+            if !self.errors.is_empty() {
+                ui.add_space(10.0);
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new(format!("❗ Ошибки: {}", self.errors.len()))
+                        .strong().color(egui::Color32::from_rgb(248, 100, 73)));
+
+                    egui::ScrollArea::vertical().id_source("errors").max_height(200.0).show(ui, |ui| {
+                        for error in &self.errors {
+                            ui.group(|ui| {
+                                ui.label(egui::RichText::new(error.kind.label()).strong());
+                                ui.label(&error.message);
+                                ui.label(egui::RichText::new(&error.path)
+                                    .small().color(egui::Color32::GRAY));
+                            });
+                        }
+                    });
+                });
+            }
+
+            // Подавленные allowlist-ом
+            if !self.suppressed.is_empty() {
+                ui.add_space(10.0);
+                ui.group(|ui| {
+                    ui.label(egui::RichText::new(format!("🔕 Подавлено allowlist: {}", self.suppressed.len()))
+                        .strong().color(egui::Color32::GRAY));
+
+                    egui::ScrollArea::vertical().id_source("suppressed").max_height(200.0).show(ui, |ui| {
+                        for threat in &self.suppressed {
                             ui.group(|ui| {
-                                ui.label(egui::RichText::new(&threat.name)
-                                    .strong().color(egui::Color32::RED));
-                                ui.label(format!("Тип: {}", threat.cheat_type));
-                                for detail in &threat.details {
-                                    ui.label(detail);
+                                ui.label(egui::RichText::new(&threat.name).strong());
+                                ui.label(format!("Сработало: {}", threat.cheat_type));
+                                if let Some(ref reason) = threat.suppressed {
+                                    ui.label(format!("Причина: {}", reason));
                                 }
                                 ui.label(egui::RichText::new(&threat.path)
                                     .small().color(egui::Color32::GRAY));
@@ -254,6 +422,22 @@ impl eframe::App for CheatDetectorApp {
                     });
                 });
             }
+
+            // Все моды из проверенных jar: что стоит у игрока, даже если угроз нет
+            if !self.jars.is_empty() {
+                ui.add_space(10.0);
+                egui::CollapsingHeader::new(format!("📦 Моды: {}", self.jars.iter().map(|j| j.mods.len()).sum::<usize>()))
+                    .show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_source("jars").max_height(200.0).show(ui, |ui| {
+                            for jar in &self.jars {
+                                for m in &jar.mods {
+                                    ui.label(format!("{} {} ({})", m.id, m.version.as_deref().unwrap_or(""), m.loader));
+                                }
+                                ui.label(egui::RichText::new(&jar.path).small().color(egui::Color32::GRAY));
+                            }
+                        });
+                    });
+            }
         });
 
         if self.scanning {
@@ -262,34 +446,211 @@ impl eframe::App for CheatDetectorApp {
     }
 }
 
+fn threat_card(ui: &mut egui::Ui, threat: &ThreatResult, title_color: egui::Color32) {
+    ui.group(|ui| {
+        ui.label(egui::RichText::new(&threat.name)
+            .strong().color(title_color));
+        ui.label(format!("{}: {} (уверенность {}%)",
+            threat.kind.label(), threat.cheat_type, threat.confidence));
+        for detail in &threat.details {
+            ui.label(detail);
+        }
+        for line in threat.metadata.summary() {
+            ui.label(egui::RichText::new(line).color(egui::Color32::LIGHT_BLUE));
+        }
+        if threat.matches.len() > 1 {
+            let others: Vec<String> = threat.matches[1..].iter()
+                .map(|m| format!("{} ({}%)", m.cheat_type, m.confidence))
+                .collect();
+            ui.label(egui::RichText::new(format!("Другие кандидаты: {}", others.join(", ")))
+                .color(egui::Color32::from_rgb(248, 180, 73)));
+        }
+        ui.label(egui::RichText::new(&threat.path)
+            .small().color(egui::Color32::GRAY));
+        if !threat.hashes.is_empty() {
+            ui.label(egui::RichText::new(format!("SHA-256: {}", threat.hashes.sha256))
+                .small().monospace().color(egui::Color32::GRAY));
+        }
+    });
+}
+
+fn verification_summary(ui: &mut egui::Ui, report: &ScanReport, result: &Verification) {
+    let color = match result {
+        Verification::Trusted => egui::Color32::GREEN,
+        Verification::Altered | Verification::Invalid(_) => egui::Color32::from_rgb(248, 100, 73),
+        Verification::Unsigned | Verification::UnknownKey(_) => egui::Color32::from_rgb(248, 180, 73),
+    };
+    ui.label(egui::RichText::new(result.label()).strong().color(color));
+    // Содержимое показываем из проверенных данных, а не из того, что видно в HTML
+    ui.label(format!("Пути: {}", report.paths.join(", ")));
+    ui.label(format!("Начало: {} | Длительность: {:.1} с",
+        sbcheats_scanner::report::format_timestamp(report.started_at),
+        report.duration_ms as f64 / 1000.0));
+    ui.label(format!("Сканер: {} | База: {}", report.scanner_version, report.database_version));
+    ui.label(format!("Проверено: {}/{} | Угроз: {} | Подозрительных: {} | Подавлено: {} | Ошибок: {}",
+        report.stats.checked, report.stats.total, report.stats.found,
+        report.stats.suspicious, report.stats.suppressed, report.errors.len()));
+    ui.label(format!("Итог: {}", report.verdict().label()));
+    if let Some(code) = report.verification_code() {
+        ui.label(egui::RichText::new(format!("Фраза: \"{}\" | Код проверки: {}",
+            report.challenge.as_deref().unwrap_or(""), code)).strong().monospace());
+    }
+    for threat in report.threats.iter().filter(|t| t.suppressed.is_none()) {
+        ui.label(egui::RichText::new(format!("{} - {}: {} ({}%)",
+            threat.name, threat.kind.label(), threat.cheat_type, threat.confidence))
+            .small().color(egui::Color32::GRAY));
+    }
+}
+
 impl CheatDetectorApp {
+    // Загружаем базу из файла; при отсутствии файла или ошибке - встроенная
+    fn reload_database(&mut self) {
+        let path = PathBuf::from(&self.database_path);
+        let mut errors = Vec::new();
+
+        let detector = if self.database_path.trim().is_empty() || !path.is_file() {
+            CheatDetector::new()
+        } else {
+            match CheatDetector::from_file(&path) {
+                Ok(detector) => detector,
+                Err(e) => {
+                    errors.push(e);
+                    CheatDetector::new()
+                }
+            }
+        };
+
+        let (allowlist, allowlist_error) = Allowlist::load_default();
+        errors.extend(allowlist_error);
+        self.detector = detector.with_allowlist(allowlist);
+
+        self.signer = match ReportSigner::load_default() {
+            Some(Ok(signer)) => Some(signer),
+            Some(Err(e)) => {
+                errors.push(e);
+                None
+            }
+            None => None,
+        };
+        // Ключ своей сборки не добавляется в доверенные: такой же лежит у каждого игрока
+        let (trusted_keys, trusted_error) = TrustedKeys::load_default();
+        errors.extend(trusted_error);
+        self.trusted_keys = trusted_keys;
+        self.database_error = (!errors.is_empty()).then(|| errors.join("; "));
+
+        let source = match self.detector.database_source() {
+            Some(p) => p.display().to_string(),
+            None => "встроенная".to_string(),
+        };
+        self.database_status = format!(
+            "Сигнатур: {} | Версия: {} | Источник: {} | Allowlist: {} | Подпись отчётов: {}",
+            self.detector.signature_count(),
+            self.detector.database_version(),
+            source,
+            self.detector.allowlist().entries.len(),
+            match self.signer {
+                Some(ref signer) => format!("ключ {}…", &signer.public_key()[..16]),
+                None => "нет ключа".to_string(),
+            },
+        );
+    }
+
     fn start_scan(&mut self) {
+        let paths: Vec<PathBuf> = if self.scan_all_instances {
+            self.instances.iter()
+                .map(Instance::mods_dir)
+                .filter(|dir| dir.is_dir())
+                .collect()
+        } else {
+            vec![PathBuf::from(&self.search_path)]
+        };
+        // Пустой список папок не проверяет ничего - такой итог нельзя показывать как "чисто"
+        if paths.is_empty() {
+            self.scan_error = Some("Не найдено ни одного инстанса с папкой mods, укажите путь вручную".to_string());
+            return;
+        }
+        self.scan_error = None;
+
         self.threats.clear();
-        self.stats = ScanStats { total: 0, checked: 0, found: 0 };
+        self.suspicious.clear();
+        self.suppressed.clear();
+        self.errors.clear();
+        self.jars.clear();
+        self.stats = ScanStats::default();
         self.progress = 0.0;
         self.scanning = true;
         self.scan_start = Some(Instant::now());
-
-        let path = PathBuf::from(&self.search_path);
-        let detector = CheatDetector::new(); // ИСПОЛЬЗУЕМ detector.rs!
+        self.scan_started_at = SystemTime::now();
+        self.scan_duration = None;
+        self.verification_code = None;
+        self.export_status = None;
+        self.scanned_paths = paths.clone();
+        self.scan_challenge = Some(self.challenge.trim().to_string()).filter(|c| !c.is_empty());
+        let scanner = Scanner::new(self.detector.clone());
         let (sender, receiver) = mpsc::channel();
         self.receiver = Some(receiver);
-
-        let cancel = Arc::new(AtomicBool::new(false));
-        self.cancel_flag = Some(cancel.clone());
+        self.scanner = Some(scanner.clone());
         let num_threads = self.num_threads;
+        let launch_analysis = self.launch_analysis || self.scan_all_instances;
 
         thread::spawn(move || {
-            let files = find_jar_files(&path);
-            sender.send(ScanMessage::Stats(ScanStats {
-                total: files.len(),
-                checked: 0,
-                found: 0,
-            })).ok();
-
-            scan_files(detector, files, sender, cancel, num_threads);
+            let scanner = if launch_analysis {
+                scanner.with_launch_analysis(launchers::discover())
+            } else {
+                scanner
+            };
+            scanner.scan_paths(&paths, sender, num_threads);
         });
     }
+
+    // Отчёт по последней проверке; база - та, которой проверяли, даже если её уже перезагрузили
+    fn build_report(&self) -> ScanReport {
+        let detector = self.scanner.as_ref().map_or(&self.detector, |s| s.detector());
+        let mut report = ScanReport::new(detector, &self.scanned_paths, self.scan_started_at);
+        report.stats = self.stats.clone();
+        report.threats = self.threats.iter()
+            .chain(&self.suspicious)
+            .chain(&self.suppressed)
+            .cloned()
+            .collect();
+        report.errors = self.errors.clone();
+        report.jars = self.jars.clone();
+        report.challenge = self.scan_challenge.clone();
+        report.finish(self.scan_duration.unwrap_or_default());
+        report
+    }
+
+    fn verify_report(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Отчёт", &["json", "html", "htm"])
+            .pick_file() {
+            self.verification = Some(signing::verify_file(&path, &self.trusted_keys));
+        }
+    }
+
+    fn export_report(&mut self) {
+        let Some(mut path) = rfd::FileDialog::new()
+            .set_file_name("sbcheats-report.html")
+            .add_filter("HTML", &["html"])
+            .add_filter("JSON", &["json"])
+            .add_filter("CSV", &["csv"])
+            .save_file() else {
+            return;
+        };
+        // Без расширения - HTML: его проще всего открыть модератору
+        let format = ReportFormat::from_path(&path).unwrap_or_else(|| {
+            path.set_extension(ReportFormat::Html.extension());
+            ReportFormat::Html
+        });
+        let mut report = self.build_report();
+        let signed = match self.signer {
+            Some(ref signer) => signer.sign(&mut report),
+            None => Ok(()),
+        };
+        self.export_status = Some(signed
+            .and_then(|_| report.save(&path, format))
+            .map(|_| path.display().to_string()));
+    }
 }
 
 fn main() -> Result<(), eframe::Error> {
@@ -308,4 +669,4 @@ fn main() -> Result<(), eframe::Error> {
         options,
         Box::new(|_| Box::<CheatDetectorApp>::default()),
     )
-}
\ No newline at end of file
+}
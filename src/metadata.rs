@@ -0,0 +1,330 @@
+// metadata.rs - Метаданные загрузчиков внутри jar
+use std::collections::BTreeMap;
+use std::io::{Read, Seek};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::ZipArchive;
+
+const FABRIC_MOD_JSON: &str = "fabric.mod.json";
+const QUILT_MOD_JSON: &str = "quilt.mod.json";
+const FORGE_MODS_TOMLS: [(&str, &str); 2] = [
+    ("forge", "META-INF/mods.toml"),
+    ("neoforge", "META-INF/neoforge.mods.toml"),
+];
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+// Ограничение на размер читаемого файла метаданных
+const MAX_METADATA_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModMetadata {
+    // fabric / quilt / forge / neoforge
+    pub loader: String,
+    pub id: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub authors: Vec<String>,
+    // Категория -> классы (main, client, server, ...)
+    pub entrypoints: BTreeMap<String, Vec<String>>,
+    // "*", "client", "server"
+    pub environment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JarMetadata {
+    pub mods: Vec<ModMetadata>,
+    // Главные атрибуты MANIFEST.MF, ключи в нижнем регистре
+    pub manifest: BTreeMap<String, String>,
+}
+
+impl JarMetadata {
+    pub fn read<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Self {
+        let manifest = read_text(archive, MANIFEST)
+            .map(|text| parse_manifest(&text))
+            .unwrap_or_default();
+
+        let mut mods = Vec::new();
+        if let Some(m) = read_text(archive, FABRIC_MOD_JSON).and_then(|t| parse_fabric(&t)) {
+            mods.push(m);
+        }
+        if let Some(m) = read_text(archive, QUILT_MOD_JSON).and_then(|t| parse_quilt(&t)) {
+            mods.push(m);
+        }
+        for (loader, name) in FORGE_MODS_TOMLS {
+            if let Some(text) = read_text(archive, name) {
+                mods.extend(parse_mods_toml(&text, loader, &manifest));
+            }
+        }
+
+        Self { mods, manifest }
+    }
+
+    pub fn has_mod_id(&self, id: &str) -> bool {
+        self.mods.iter().any(|m| m.id.eq_ignore_ascii_case(id))
+    }
+
+    // Значения по ключу для Rule::Metadata: поля модов или атрибут манифеста
+    pub fn values(&self, key: &str) -> Vec<&str> {
+        let key = key.to_lowercase();
+        match key.as_str() {
+            "mod_id" => self.mods.iter().map(|m| m.id.as_str()).collect(),
+            "mod_name" => self.mods.iter().filter_map(|m| m.name.as_deref()).collect(),
+            "mod_version" => self.mods.iter().filter_map(|m| m.version.as_deref()).collect(),
+            "author" => self.mods.iter().flat_map(|m| m.authors.iter().map(String::as_str)).collect(),
+            "environment" => self.mods.iter().filter_map(|m| m.environment.as_deref()).collect(),
+            "entrypoint" => self.mods.iter()
+                .flat_map(|m| m.entrypoints.values().flatten().map(String::as_str))
+                .collect(),
+            _ => self.manifest.get(&key).map(String::as_str).into_iter().collect(),
+        }
+    }
+
+    // Короткие строки для отчёта
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.mods.iter()
+            .map(|m| {
+                let mut line = format!("Мод ({}): {}", m.loader, m.id);
+                if let Some(ref version) = m.version {
+                    line.push_str(&format!(" {}", version));
+                }
+                if let Some(ref name) = m.name {
+                    line.push_str(&format!(" \"{}\"", name));
+                }
+                if !m.authors.is_empty() {
+                    line.push_str(&format!(" [{}]", m.authors.join(", ")));
+                }
+                line
+            })
+            .collect();
+
+        if let Some(main_class) = self.manifest.get("main-class") {
+            lines.push(format!("Main-Class: {}", main_class));
+        }
+        lines
+    }
+}
+
+fn read_text<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let file = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    file.take(MAX_METADATA_SIZE).read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// Главная секция манифеста (до первой пустой строки)
+pub fn parse_manifest(text: &str) -> BTreeMap<String, String> {
+    let mut attributes: BTreeMap<String, String> = BTreeMap::new();
+    let mut last_key: Option<String> = None;
+
+    for line in text.lines() {
+        if line.is_empty() {
+            break;
+        }
+        // Строка-продолжение начинается с пробела
+        if let Some(cont) = line.strip_prefix(' ') {
+            if let Some(value) = last_key.as_ref().and_then(|k| attributes.get_mut(k)) {
+                value.push_str(cont);
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().to_lowercase();
+            attributes.insert(key.clone(), value.trim().to_string());
+            last_key = Some(key);
+        }
+    }
+    attributes
+}
+
+// Автор в fabric/quilt: строка или объект {"name": ...}
+fn person_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(obj) => obj.get("name").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+}
+
+// Entrypoint: строка или объект {"value": ...}
+fn parse_entrypoints(value: Option<&Value>) -> BTreeMap<String, Vec<String>> {
+    let Some(Value::Object(categories)) = value else {
+        return BTreeMap::new();
+    };
+
+    categories.iter()
+        .map(|(category, list)| {
+            let items = match list {
+                Value::Array(items) => items.iter().collect(),
+                other => vec![other],
+            };
+            let classes = items.into_iter()
+                .filter_map(|item| match item {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Object(obj) => obj.get("value").and_then(Value::as_str).map(str::to_string),
+                    _ => None,
+                })
+                .collect();
+            (category.clone(), classes)
+        })
+        .collect()
+}
+
+fn parse_fabric(text: &str) -> Option<ModMetadata> {
+    let json: Value = serde_json::from_str(text).ok()?;
+    let str_field = |key: &str| json.get(key).and_then(Value::as_str).map(str::to_string);
+
+    Some(ModMetadata {
+        loader: "fabric".to_string(),
+        id: str_field("id")?,
+        name: str_field("name"),
+        version: str_field("version"),
+        authors: json.get("authors")
+            .and_then(Value::as_array)
+            .map(|list| list.iter().filter_map(person_name).collect())
+            .unwrap_or_default(),
+        entrypoints: parse_entrypoints(json.get("entrypoints")),
+        environment: str_field("environment"),
+    })
+}
+
+fn parse_quilt(text: &str) -> Option<ModMetadata> {
+    let json: Value = serde_json::from_str(text).ok()?;
+    let loader = json.get("quilt_loader")?;
+    let str_field = |key: &str| loader.get(key).and_then(Value::as_str).map(str::to_string);
+    let meta = loader.get("metadata");
+
+    Some(ModMetadata {
+        loader: "quilt".to_string(),
+        id: str_field("id")?,
+        name: meta.and_then(|m| m.get("name")).and_then(Value::as_str).map(str::to_string),
+        version: str_field("version"),
+        // contributors: {"Имя": "Роль"}
+        authors: meta.and_then(|m| m.get("contributors"))
+            .and_then(Value::as_object)
+            .map(|c| c.keys().cloned().collect())
+            .unwrap_or_default(),
+        entrypoints: parse_entrypoints(loader.get("entrypoints")),
+        environment: json.get("minecraft")
+            .and_then(|m| m.get("environment"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+fn parse_mods_toml(text: &str, loader: &str, manifest: &BTreeMap<String, String>) -> Vec<ModMetadata> {
+    let Ok(doc) = text.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(mods) = doc.get("mods").and_then(toml::Value::as_array) else {
+        return Vec::new();
+    };
+
+    let file_authors = doc.get("authors").and_then(toml::Value::as_str);
+
+    mods.iter()
+        .filter_map(|entry| {
+            let str_field = |key: &str| entry.get(key).and_then(toml::Value::as_str).map(str::to_string);
+
+            // ${file.jarVersion} подставляется из Implementation-Version
+            let version = str_field("version").map(|v| {
+                match manifest.get("implementation-version") {
+                    Some(impl_version) if v.contains("${file.jarVersion}") => {
+                        v.replace("${file.jarVersion}", impl_version)
+                    }
+                    _ => v,
+                }
+            });
+
+            let authors = str_field("authors")
+                .or_else(|| file_authors.map(str::to_string))
+                .map(|a| a.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+                .unwrap_or_default();
+
+            Some(ModMetadata {
+                loader: loader.to_string(),
+                id: str_field("modId")?,
+                name: str_field("displayName"),
+                version,
+                authors,
+                entrypoints: BTreeMap::new(),
+                environment: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::testutil::jar;
+
+    fn read(entries: &[(&str, &[u8])]) -> JarMetadata {
+        JarMetadata::read(&mut ZipArchive::new(Cursor::new(jar(entries))).unwrap())
+    }
+
+    #[test]
+    fn quilt_contributors_and_environment() {
+        let m = parse_quilt(r#"{
+            "schema_version": 1,
+            "quilt_loader": {
+                "id": "freecam",
+                "version": "1.2.0",
+                "metadata": {"name": "Freecam", "contributors": {"hashalite": "Owner", "zergatul": "Contributor"}},
+                "entrypoints": {"client": "net.xolt.freecam.Freecam"}
+            },
+            "minecraft": {"environment": "client"}
+        }"#).unwrap();
+        assert_eq!((m.loader.as_str(), m.id.as_str(), m.name.as_deref()), ("quilt", "freecam", Some("Freecam")));
+        assert_eq!(m.authors, ["hashalite", "zergatul"]);
+        assert_eq!(m.environment.as_deref(), Some("client"));
+        assert_eq!(m.entrypoints["client"], ["net.xolt.freecam.Freecam"]);
+    }
+
+    #[test]
+    fn mods_toml_version_from_manifest_and_file_authors() {
+        let manifest = parse_manifest("Manifest-Version: 1.0\r\nImplementation-Version: 1.2.0\r\n");
+        let mods = parse_mods_toml(r#"
+            modLoader = "javafml"
+            authors = "alice, bob"
+
+            [[mods]]
+            modId = "freecam"
+            version = "${file.jarVersion}"
+            displayName = "Freecam"
+
+            [[mods]]
+            modId = "freecamlib"
+            version = "2.0"
+            authors = "carol"
+        "#, "forge", &manifest);
+
+        assert_eq!(mods.len(), 2);
+        assert_eq!((mods[0].id.as_str(), mods[0].version.as_deref()), ("freecam", Some("1.2.0")));
+        assert_eq!(mods[0].authors, ["alice", "bob"]);
+        assert_eq!(mods[1].authors, ["carol"]);
+    }
+
+    #[test]
+    fn neoforge_mods_toml_is_read() {
+        let metadata = read(&[
+            ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\r\nImplementation-Version: 3.1\r\n"),
+            ("META-INF/neoforge.mods.toml", b"[[mods]]\nmodId = \"xray\"\nversion = \"${file.jarVersion}\"\n"),
+        ]);
+        assert_eq!(metadata.mods.len(), 1);
+        let m = &metadata.mods[0];
+        assert_eq!((m.loader.as_str(), m.id.as_str(), m.version.as_deref()), ("neoforge", "xray", Some("3.1")));
+    }
+
+    #[test]
+    fn manifest_continuation_and_main_section_only() {
+        let manifest = parse_manifest("Manifest-Version: 1.0\r\n\
+            Main-Class: net.example.very.long.package.na\r\n me.Main\r\n\
+            \r\n\
+            Name: net/example/Other.class\r\n\
+            Main-Class: ignored.Main\r\n");
+        assert_eq!(manifest.get("main-class").map(String::as_str), Some("net.example.very.long.package.name.Main"));
+        assert_eq!(manifest.len(), 2);
+        assert!(!manifest.contains_key("name"));
+    }
+}
@@ -0,0 +1,345 @@
+// mixin.rs - Анализ Mixin-конфигов и целей инъекций
+use std::io::{Read, Seek};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::ZipArchive;
+use crate::classfile::{internal_name, ClassFile};
+
+const MIXIN_ANNOTATION: &str = "Lorg/spongepowered/asm/mixin/Mixin;";
+const OVERWRITE_ANNOTATION: &str = "Lorg/spongepowered/asm/mixin/Overwrite;";
+const MAX_CONFIG_SIZE: u64 = 1024 * 1024;
+const MAX_MIXIN_CLASS_SIZE: u64 = 1024 * 1024;
+
+// Сумма весов, начиная с которой jar считается подозрительным
+pub const SUSPICIOUS_SCORE: u32 = 40;
+
+// Классы Minecraft, в которых ищутся точки инъекции читов: имена yarn, intermediary,
+// mojmap и MCP. Сам миксин в такой класс ничего не значит: моды рендера и оптимизации
+// (Sodium, Iris, Lithium) лезут в те же Entity, GameRenderer и WorldRenderer
+const SENSITIVE_TARGETS: &[&str] = &[
+    // Взаимодействие игрока
+    "net/minecraft/client/network/ClientPlayerInteractionManager",
+    "net/minecraft/class_636",
+    "net/minecraft/client/multiplayer/MultiPlayerGameMode",
+    "net/minecraft/client/multiplayer/PlayerControllerMP",
+    "net/minecraft/client/multiplayer/PlayerController",
+    // Клиент целиком: клик атаки, подсветка
+    "net/minecraft/client/MinecraftClient",
+    "net/minecraft/class_310",
+    "net/minecraft/client/Minecraft",
+    // Сетевые пакеты
+    "net/minecraft/network/ClientConnection",
+    "net/minecraft/class_2535",
+    "net/minecraft/network/Connection",
+    "net/minecraft/network/NetworkManager",
+    "net/minecraft/client/network/ClientPlayNetworkHandler",
+    "net/minecraft/class_634",
+    "net/minecraft/client/multiplayer/ClientPacketListener",
+    "net/minecraft/client/network/NetHandlerPlayClient",
+    // Сущности
+    "net/minecraft/entity/Entity",
+    "net/minecraft/class_1297",
+    "net/minecraft/world/entity/Entity",
+    "net/minecraft/entity/LivingEntity",
+    "net/minecraft/class_1309",
+    "net/minecraft/world/entity/LivingEntity",
+    "net/minecraft/entity/EntityLivingBase",
+    // Выбор цели
+    "net/minecraft/client/render/GameRenderer",
+    "net/minecraft/class_757",
+    "net/minecraft/client/renderer/GameRenderer",
+    "net/minecraft/client/renderer/EntityRenderer",
+    // Локальный игрок
+    "net/minecraft/client/network/ClientPlayerEntity",
+    "net/minecraft/class_746",
+    "net/minecraft/client/player/LocalPlayer",
+    "net/minecraft/client/entity/EntityPlayerSP",
+];
+
+// Методы, которые меняют читы: (имя yarn / intermediary / mojmap / MCP, категория, вес)
+const INJECTION_POINTS: &[(&str, &str, u32)] = &[
+    // Удар и дистанция: reach, autoclicker
+    ("attackEntity", "атака и дистанция", 40),
+    ("method_2918", "атака и дистанция", 40),
+    ("attack", "атака и дистанция", 40),
+    ("getReachDistance", "атака и дистанция", 40),
+    ("method_2904", "атака и дистанция", 40),
+    ("getPickRange", "атака и дистанция", 40),
+    ("getBlockReachDistance", "атака и дистанция", 40),
+    ("doAttack", "атака и дистанция", 40),
+    ("method_1536", "атака и дистанция", 40),
+    ("startAttack", "атака и дистанция", 40),
+    ("clickMouse", "атака и дистанция", 40),
+    // Хитбоксы сущностей
+    ("getTargetingMargin", "хитбоксы", 40),
+    ("method_5871", "хитбоксы", 40),
+    ("getPickRadius", "хитбоксы", 40),
+    ("getCollisionBorderSize", "хитбоксы", 40),
+    // Выбор цели
+    ("updateTargetedEntity", "выбор цели", 30),
+    ("method_3190", "выбор цели", 30),
+    ("pick", "выбор цели", 30),
+    ("getMouseOver", "выбор цели", 30),
+    // Отдача и скорость: velocity, anti-knockback
+    ("setVelocityClient", "отдача", 40),
+    ("method_5750", "отдача", 40),
+    ("takeKnockback", "отдача", 40),
+    ("method_6005", "отдача", 40),
+    ("knockback", "отдача", 40),
+    ("knockBack", "отдача", 40),
+    ("pushAwayFrom", "отдача", 40),
+    ("method_5697", "отдача", 40),
+    ("onEntityVelocityUpdate", "отдача", 40),
+    ("handleSetEntityMotion", "отдача", 40),
+    ("handleEntityVelocity", "отдача", 40),
+    ("onExplosion", "отдача", 40),
+    ("handleExplosion", "отдача", 40),
+    // Подсветка сущностей сквозь стены (ESP)
+    ("isGlowing", "подсветка сущностей", 30),
+    ("method_5851", "подсветка сущностей", 30),
+    ("isCurrentlyGlowing", "подсветка сущностей", 30),
+    ("hasOutline", "подсветка сущностей", 30),
+    ("method_27022", "подсветка сущностей", 30),
+    ("shouldEntityAppearGlowing", "подсветка сущностей", 30),
+    // Отправка и приём пакетов
+    ("send", "сетевые пакеты", 30),
+    ("method_10743", "сетевые пакеты", 30),
+    ("sendPacket", "сетевые пакеты", 30),
+    ("channelRead0", "сетевые пакеты", 30),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MixinTarget {
+    pub mixin: String,
+    pub target: String,
+    // Метод цели из @Inject(method = ...), @Redirect и т.п.
+    pub method: String,
+    pub category: String,
+    pub weight: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MixinReport {
+    pub configs: Vec<String>,
+    pub mixin_classes: usize,
+    pub suspicious: Vec<MixinTarget>,
+    // Сумма весов по уникальным категориям, не больше 100
+    pub score: u32,
+}
+
+impl MixinReport {
+    pub fn is_suspicious(&self) -> bool {
+        self.score >= SUSPICIOUS_SCORE
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        self.suspicious.iter()
+            .map(|t| format!("Mixin {} → {}.{} ({})", t.mixin, t.target, t.method, t.category))
+            .collect()
+    }
+}
+
+// "*.mixins.json", "mixins.*.json" в любом месте архива
+fn is_mixin_config(name: &str) -> bool {
+    let file_name = name.rsplit('/').next().unwrap_or(name).to_lowercase();
+    file_name.ends_with(".json") && file_name.contains("mixins")
+}
+
+// После deadline классы больше не читаются - вызывающий отметит нехватку времени
+pub fn analyze<R: Read + Seek>(archive: &mut ZipArchive<R>, deadline: Instant) -> MixinReport {
+    let mut report = MixinReport::default();
+
+    let configs: Vec<String> = archive.file_names()
+        .filter(|name| is_mixin_config(name))
+        .map(str::to_string)
+        .collect();
+
+    for config_name in configs {
+        let Some(config) = read_json(archive, &config_name) else { continue };
+        let Some(package) = config.get("package").and_then(Value::as_str) else { continue };
+        report.configs.push(config_name);
+
+        let class_names: Vec<String> = ["mixins", "client", "server"].iter()
+            .filter_map(|key| config.get(*key).and_then(Value::as_array))
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(|name| format!("{}/{}.class", internal_name(package), internal_name(name)))
+            .collect();
+
+        for class_path in class_names {
+            if Instant::now() > deadline {
+                break;
+            }
+            let Some(class) = read_class(archive, &class_path) else { continue };
+            report.mixin_classes += 1;
+
+            let mixin_name = class_path.trim_end_matches(".class").to_string();
+            let targets: Vec<String> = class.annotations.iter()
+                .filter(|a| a.type_descriptor == MIXIN_ANNOTATION)
+                .flat_map(|a| a.values("value").chain(a.values("targets")))
+                .map(internal_name)
+                .filter(|target| SENSITIVE_TARGETS.contains(&target.as_str()))
+                .collect();
+            if targets.is_empty() {
+                continue;
+            }
+            for method in injected_methods(&class) {
+                let Some((_, category, weight)) = INJECTION_POINTS.iter().find(|(name, _, _)| *name == method) else {
+                    continue;
+                };
+                for target in &targets {
+                    report.suspicious.push(MixinTarget {
+                        mixin: mixin_name.clone(),
+                        target: target.clone(),
+                        method: method.clone(),
+                        category: category.to_string(),
+                        weight: *weight,
+                    });
+                }
+            }
+        }
+    }
+
+    // Несколько миксинов в одну категорию не накручивают score
+    let mut categories: Vec<(&str, u32)> = report.suspicious.iter()
+        .map(|t| (t.category.as_str(), t.weight))
+        .collect();
+    categories.sort();
+    categories.dedup_by_key(|(category, _)| *category);
+    report.score = categories.iter().map(|(_, w)| w).sum::<u32>().min(100);
+
+    report
+}
+
+// Методы цели, в которые внедряется миксин: method = "..." у любого инжектора
+// (@Inject, @Redirect, @ModifyArg, MixinExtras) и имя метода с @Overwrite
+fn injected_methods(class: &ClassFile) -> Vec<String> {
+    let mut methods = Vec::new();
+    for method in &class.methods {
+        for annotation in &method.annotations {
+            if annotation.type_descriptor == OVERWRITE_ANNOTATION {
+                methods.push(method.name.clone());
+            }
+            methods.extend(annotation.values("method").map(|reference| method_name(reference).to_string()));
+        }
+    }
+    methods.sort();
+    methods.dedup();
+    methods
+}
+
+// "Lnet/minecraft/class_1297;method_5871()F" -> "method_5871", "attack*" -> "attack"
+fn method_name(reference: &str) -> &str {
+    let name = reference.split('(').next().unwrap_or(reference);
+    name.rsplit(';').next().unwrap_or(name).trim_end_matches('*')
+}
+
+fn read_json<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<Value> {
+    let file = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    file.take(MAX_CONFIG_SIZE).read_to_end(&mut bytes).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn read_class<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<ClassFile> {
+    let file = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    file.take(MAX_MIXIN_CLASS_SIZE).read_to_end(&mut bytes).ok()?;
+    ClassFile::parse(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use crate::testutil::{jar, ClassBuilder, Element};
+
+    const INJECT: &str = "Lorg/spongepowered/asm/mixin/injection/Inject;";
+    const REDIRECT: &str = "Lorg/spongepowered/asm/mixin/injection/Redirect;";
+
+    // Класс миксина: цель в @Mixin и методы с инжекторами (аннотация, method = ...)
+    fn mixin_class(name: &str, target: &str, injections: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = ClassBuilder::new(name);
+        let mixin = builder.annotation(MIXIN_ANNOTATION, &[("value", Element::Array(vec![Element::Class(target)]))]);
+        let mut builder = builder.class_annotation(mixin);
+        for (i, (annotation, method)) in injections.iter().enumerate() {
+            let injector = builder.annotation(annotation, &[("method", Element::Array(vec![Element::Str(method)]))]);
+            builder = builder.method(&format!("handler{}", i), vec![injector]);
+        }
+        builder.build()
+    }
+
+    fn analyze_mixins(classes: &[(&str, Vec<u8>)]) -> MixinReport {
+        let names: Vec<String> = classes.iter()
+            .map(|(name, _)| format!("\"{}\"", name.rsplit('/').next().unwrap()))
+            .collect();
+        let config = format!("{{\"package\": \"mod.mixin\", \"client\": [{}]}}", names.join(", "));
+        let mut entries: Vec<(String, &[u8])> = classes.iter()
+            .map(|(name, bytes)| (format!("{}.class", name), bytes.as_slice()))
+            .collect();
+        entries.push(("mod.mixins.json".to_string(), config.as_bytes()));
+        let entries: Vec<(&str, &[u8])> = entries.iter().map(|(n, b)| (n.as_str(), *b)).collect();
+        let mut archive = ZipArchive::new(Cursor::new(jar(&entries))).unwrap();
+        analyze(&mut archive, Instant::now() + std::time::Duration::from_secs(30))
+    }
+
+    #[test]
+    fn rendering_mixins_stay_below_threshold() {
+        // Как у Sodium / Iris / Lithium: те же классы, но методы рендера и тика
+        let report = analyze_mixins(&[
+            ("mod/mixin/GameRendererMixin", mixin_class("mod/mixin/GameRendererMixin",
+                "net/minecraft/class_757", &[(INJECT, "renderWorld"), (INJECT, "method_3188")])),
+            ("mod/mixin/EntityMixin", mixin_class("mod/mixin/EntityMixin",
+                "net/minecraft/entity/Entity", &[(INJECT, "shouldRender(DDD)Z"), (REDIRECT, "baseTick")])),
+            ("mod/mixin/WorldRendererMixin", mixin_class("mod/mixin/WorldRendererMixin",
+                "net/minecraft/client/render/WorldRenderer", &[(INJECT, "render"), (INJECT, "setupTerrain")])),
+            ("mod/mixin/ClientPlayerMixin", mixin_class("mod/mixin/ClientPlayerMixin",
+                "net/minecraft/class_746", &[(INJECT, "tick")])),
+        ]);
+        assert_eq!(report.mixin_classes, 4);
+        assert!(report.suspicious.is_empty(), "{:?}", report.suspicious);
+        assert!(!report.is_suspicious());
+    }
+
+    #[test]
+    fn flags_hitbox_and_reach_injections() {
+        let report = analyze_mixins(&[
+            ("mod/mixin/HitboxMixin", mixin_class("mod/mixin/HitboxMixin",
+                "net/minecraft/class_1297", &[(INJECT, "Lnet/minecraft/class_1297;method_5871()F")])),
+            ("mod/mixin/ReachMixin", mixin_class("mod/mixin/ReachMixin",
+                "net/minecraft/client/network/ClientPlayerInteractionManager", &[(REDIRECT, "getReachDistance")])),
+        ]);
+        assert!(report.is_suspicious());
+        assert_eq!(report.score, 80);
+        let methods: Vec<&str> = report.suspicious.iter().map(|t| t.method.as_str()).collect();
+        assert_eq!(methods, ["method_5871", "getReachDistance"]);
+    }
+
+    #[test]
+    fn single_packet_hook_is_not_enough() {
+        let report = analyze_mixins(&[
+            ("mod/mixin/ConnectionMixin", mixin_class("mod/mixin/ConnectionMixin",
+                "net/minecraft/network/ClientConnection", &[(INJECT, "send")])),
+        ]);
+        assert_eq!(report.score, 30);
+        assert!(!report.is_suspicious());
+    }
+
+    #[test]
+    fn overwrite_counts_as_injection_point() {
+        let mut builder = ClassBuilder::new("mod/mixin/VelocityMixin");
+        let mixin = builder.annotation(MIXIN_ANNOTATION, &[("value", Element::Class("net/minecraft/class_1309"))]);
+        let overwrite = builder.annotation(OVERWRITE_ANNOTATION, &[]);
+        let class = ClassFile::parse(&builder.class_annotation(mixin).method("takeKnockback", vec![overwrite]).build()).unwrap();
+        assert_eq!(injected_methods(&class), ["takeKnockback"]);
+    }
+
+    #[test]
+    fn method_reference_forms() {
+        assert_eq!(method_name("attack"), "attack");
+        assert_eq!(method_name("attack(Lnet/minecraft/class_1297;)V"), "attack");
+        assert_eq!(method_name("Lnet/minecraft/class_1297;method_5871()F"), "method_5871");
+        assert_eq!(method_name("knockback*"), "knockback");
+    }
+}
@@ -0,0 +1,186 @@
+// obfuscation.rs - Эвристики обфускации для неизвестных jar
+use std::io::{Read, Seek};
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+// Сумма баллов, начиная с которой jar считается подозрительным
+pub const SUSPICIOUS_SCORE: u32 = 40;
+
+// Слишком маленькие jar не оцениваем по доле коротких имён
+const MIN_CLASSES: usize = 10;
+// Пакет из стольких коротких сегментов подряд считается бессмысленным ("a/b/c/d/")
+const MEANINGLESS_CHAIN: usize = 4;
+// Бит на байт; у текста и байткода обычно 4-6, у шифрованных данных почти 8
+const HIGH_ENTROPY: f64 = 7.5;
+const MIN_RESOURCE_SIZE: u64 = 1024;
+const ENTROPY_SAMPLE_SIZE: u64 = 64 * 1024;
+const MAX_RESOURCES_CHECKED: usize = 200;
+
+// Форматы, которые сжаты сами по себе и всегда имеют высокую энтропию
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "webp", "ogg", "mp3", "wav", "zip", "jar", "gz", "xz",
+    "bz2", "7z", "ttf", "otf", "woff", "woff2", "so", "dll", "dylib", "jnilib", "nbt",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObfuscationReport {
+    pub classes: usize,
+    // Имена классов из 1-2 символов (z.class, ab.class)
+    pub short_names: usize,
+    // Не-ASCII и визуально путаемые имена (IlIlI, O0O0)
+    pub confusable_names: Vec<String>,
+    // Классы в цепочках вида a/b/c/d/
+    pub meaningless_packages: usize,
+    pub high_entropy_resources: Vec<String>,
+    pub score: u32,
+}
+
+impl ObfuscationReport {
+    pub fn is_suspicious(&self) -> bool {
+        self.score >= SUSPICIOUS_SCORE
+    }
+
+    pub fn short_name_share(&self) -> f32 {
+        if self.classes == 0 {
+            0.0
+        } else {
+            self.short_names as f32 / self.classes as f32
+        }
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.short_names > 0 {
+            lines.push(format!("Короткие имена классов: {}/{} ({:.0}%)",
+                self.short_names, self.classes, self.short_name_share() * 100.0));
+        }
+        if !self.confusable_names.is_empty() {
+            lines.push(format!("Не-ASCII/путаемые имена: {}", preview(&self.confusable_names)));
+        }
+        if self.meaningless_packages > 0 {
+            lines.push(format!("Классов в бессмысленных пакетах: {}", self.meaningless_packages));
+        }
+        if !self.high_entropy_resources.is_empty() {
+            lines.push(format!("Ресурсы с высокой энтропией: {}", preview(&self.high_entropy_resources)));
+        }
+        lines
+    }
+}
+
+fn preview(items: &[String]) -> String {
+    const SHOWN: usize = 3;
+    let mut text = items.iter().take(SHOWN).cloned().collect::<Vec<_>>().join(", ");
+    if items.len() > SHOWN {
+        text.push_str(&format!(" и ещё {}", items.len() - SHOWN));
+    }
+    text
+}
+
+// Только из I, l, 1 или O, 0 - типичный приём обфускаторов
+fn is_confusable(name: &str) -> bool {
+    !name.is_ascii()
+        || (name.len() >= 3 && name.chars().all(|c| matches!(c, 'I' | 'l' | '1' | '|')))
+        || (name.len() >= 3 && name.chars().all(|c| matches!(c, 'O' | '0' | 'o')))
+}
+
+fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts.iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// После deadline ресурсы больше не читаются - вызывающий отметит нехватку времени
+pub fn analyze<R: Read + Seek>(archive: &mut ZipArchive<R>, deadline: Instant) -> ObfuscationReport {
+    let mut report = ObfuscationReport::default();
+
+    let names: Vec<String> = archive.file_names().map(str::to_string).collect();
+
+    let mut resources = Vec::new();
+    for name in &names {
+        if name.ends_with('/') || name.starts_with("META-INF/") {
+            continue;
+        }
+        let Some(class_path) = name.strip_suffix(".class") else {
+            resources.push(name.clone());
+            continue;
+        };
+
+        let (package, file) = class_path.rsplit_once('/').unwrap_or(("", class_path));
+        if file == "module-info" || file == "package-info" {
+            continue;
+        }
+        report.classes += 1;
+
+        // Внутренние классы оцениваются по внешнему имени: Foo$1 -> Foo
+        let outer = file.split('$').next().unwrap_or(file);
+        if outer.chars().count() <= 2 {
+            report.short_names += 1;
+        }
+        if class_path.split('/').any(is_confusable) {
+            report.confusable_names.push(class_path.to_string());
+        }
+
+        let segments: Vec<&str> = package.split('/').filter(|s| !s.is_empty()).collect();
+        let longest_chain = segments.iter()
+            .fold((0, 0), |(best, run), s| {
+                let run = if s.chars().count() <= 2 { run + 1 } else { 0 };
+                (best.max(run), run)
+            })
+            .0;
+        if longest_chain >= MEANINGLESS_CHAIN {
+            report.meaningless_packages += 1;
+        }
+    }
+
+    for name in resources.into_iter().take(MAX_RESOURCES_CHECKED) {
+        if Instant::now() > deadline {
+            break;
+        }
+        let extension = name.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+        if COMPRESSED_EXTENSIONS.contains(&extension.as_str()) {
+            continue;
+        }
+        let Ok(file) = archive.by_name(&name) else { continue };
+        if file.size() < MIN_RESOURCE_SIZE {
+            continue;
+        }
+        let mut sample = Vec::new();
+        if file.take(ENTROPY_SAMPLE_SIZE).read_to_end(&mut sample).is_ok()
+            && shannon_entropy(&sample) >= HIGH_ENTROPY
+        {
+            report.high_entropy_resources.push(name);
+        }
+    }
+
+    let mut score = 0;
+    if report.classes >= MIN_CLASSES {
+        let share = report.short_name_share();
+        if share >= 0.5 {
+            score += 40;
+        } else if share >= 0.25 {
+            score += 25;
+        }
+    }
+    if !report.confusable_names.is_empty() {
+        score += 25;
+    }
+    if report.meaningless_packages > 0 {
+        score += 20;
+    }
+    if !report.high_entropy_resources.is_empty() {
+        score += 25;
+    }
+    report.score = score.min(100);
+
+    report
+}
@@ -0,0 +1,495 @@
+// report.rs - Полный отчёт о проверке для тикета: JSON, CSV, HTML одним файлом
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::challenge;
+use crate::detector::{CheatDetector, JarRecord, ThreatResult};
+use crate::errors::ScanError;
+use crate::metadata::ModMetadata;
+use crate::scanner::{ScanMessage, ScanStats};
+use crate::signing::{ReportSignature, HTML_REPORT_START};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    // Формат по расширению файла: report.json, report.csv, report.html
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(ReportFormat::Json),
+            "csv" => Some(ReportFormat::Csv),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Clean,
+    Threats,
+    Incomplete,
+}
+
+impl Verdict {
+    pub fn label(self) -> &'static str {
+        match self {
+            Verdict::Clean => "Угроз не найдено",
+            Verdict::Threats => "Найдены угрозы",
+            Verdict::Incomplete => "Проверка неполная, отсутствие угроз не гарантировано",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanReport {
+    pub scanner_version: String,
+    pub database_version: String,
+    // None - встроенная база
+    pub database_source: Option<String>,
+    pub paths: Vec<String>,
+    // Unix-время, секунды UTC
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    pub stats: ScanStats,
+    // Все находки: угрозы, подозрительные и подавленные allowlist-ом
+    pub threats: Vec<ThreatResult>,
+    pub errors: Vec<ScanError>,
+    // Моды из всех проверенных jar, по пути
+    #[serde(default)]
+    pub jars: Vec<JarRecord>,
+    // Фраза модератора, введённая до проверки; по ней пересчитывается код проверки
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub challenge: Option<String>,
+    // Ставит ReportSigner; CSV не подписывается - для проверки нужен JSON или HTML
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ReportSignature>,
+}
+
+impl ScanReport {
+    pub fn new(detector: &CheatDetector, paths: &[PathBuf], started_at: SystemTime) -> Self {
+        let started_at = unix_seconds(started_at);
+        Self {
+            scanner_version: env!("CARGO_PKG_VERSION").to_string(),
+            database_version: detector.database_version().to_string(),
+            database_source: detector.database_source().map(|p| p.display().to_string()),
+            paths: paths.iter().map(|p| p.display().to_string()).collect(),
+            started_at,
+            finished_at: started_at,
+            duration_ms: 0,
+            stats: ScanStats::default(),
+            threats: Vec::new(),
+            errors: Vec::new(),
+            jars: Vec::new(),
+            challenge: None,
+            signature: None,
+        }
+    }
+
+    // Сообщение сканера -> отчёт; прогресс и Complete не хранятся
+    pub fn record(&mut self, msg: &ScanMessage) {
+        match msg {
+            ScanMessage::ThreatFound(threat) => self.threats.push(threat.as_ref().clone()),
+            ScanMessage::Error(error) => self.errors.push(error.clone()),
+            ScanMessage::JarChecked(jar) => self.jars.push(jar.as_ref().clone()),
+            ScanMessage::Stats(stats) => self.stats = stats.clone(),
+            ScanMessage::ExtraPath(path) => {
+                let path = path.display().to_string();
+                if !self.paths.contains(&path) {
+                    self.paths.push(path);
+                }
+            }
+            ScanMessage::Progress(_) | ScanMessage::Complete => {}
+        }
+    }
+
+    pub fn finish(&mut self, duration: Duration) {
+        self.duration_ms = duration.as_millis() as u64;
+        self.finished_at = self.started_at + duration.as_secs();
+        // Потоки сканера присылают jar в разном порядке
+        self.jars.sort_by(|a, b| a.path.cmp(&b.path));
+    }
+
+    // Угроза важнее неполной проверки: найденное уже не отменить
+    pub fn verdict(&self) -> Verdict {
+        if self.stats.found + self.stats.suspicious > 0 {
+            Verdict::Threats
+        } else if !self.errors.is_empty() || self.stats.checked < self.stats.total {
+            Verdict::Incomplete
+        } else {
+            Verdict::Clean
+        }
+    }
+
+    // Тот же код, что игрок видел на экране после проверки
+    pub fn verification_code(&self) -> Option<String> {
+        let phrase = self.challenge.as_deref().filter(|c| !c.trim().is_empty())?;
+        Some(challenge::verification_code(phrase, self))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path, format: ReportFormat) -> Result<(), String> {
+        std::fs::write(path, self.render(format)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String, String> {
+        match format {
+            ReportFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+            ReportFormat::Csv => Ok(self.to_csv()),
+            ReportFormat::Html => Ok(self.to_html()),
+        }
+    }
+
+    fn to_csv(&self) -> String {
+        let mut rows: Vec<[String; 8]> = vec![[
+            "section", "kind", "path", "name", "result", "confidence", "sha256", "details",
+        ].map(String::from)];
+        let mut info = |key: &str, value: String| {
+            rows.push(["scan".into(), key.into(), String::new(), String::new(), value,
+                String::new(), String::new(), String::new()]);
+        };
+
+        info("scanner_version", self.scanner_version.clone());
+        info("database_version", self.database_version.clone());
+        info("database_source", self.database_source.clone().unwrap_or_else(|| "встроенная".to_string()));
+        for path in &self.paths {
+            info("path", path.clone());
+        }
+        info("started_at", format_timestamp(self.started_at));
+        info("duration_ms", self.duration_ms.to_string());
+        info("total", self.stats.total.to_string());
+        info("checked", self.stats.checked.to_string());
+        info("found", self.stats.found.to_string());
+        info("suspicious", self.stats.suspicious.to_string());
+        info("suppressed", self.stats.suppressed.to_string());
+        info("errors", self.errors.len().to_string());
+        info("verdict", self.verdict().label().to_string());
+        if let Some(code) = self.verification_code() {
+            info("challenge", self.challenge.clone().unwrap_or_default());
+            info("verification_code", code);
+        }
+
+        for threat in &self.threats {
+            let (section, details) = match threat.suppressed {
+                Some(ref reason) => ("suppressed", reason.clone()),
+                None if threat.is_heuristic() => ("suspicious", threat.details.join("; ")),
+                None => ("threat", threat.details.join("; ")),
+            };
+            rows.push([
+                section.into(),
+                threat.kind.label().into(),
+                threat.path.clone(),
+                threat.name.clone(),
+                threat.cheat_type.clone(),
+                threat.confidence.to_string(),
+                threat.hashes.sha256.clone(),
+                details,
+            ]);
+        }
+        for error in &self.errors {
+            rows.push(["error".into(), error.kind.label().into(), error.path.clone(), String::new(),
+                error.message.clone(), String::new(), String::new(), String::new()]);
+        }
+        for jar in &self.jars {
+            for m in &jar.mods {
+                rows.push(["mod".into(), m.loader.clone(), jar.path.clone(), jar.name.clone(),
+                    mod_title(m), String::new(), jar.sha256.clone(), m.name.clone().unwrap_or_default()]);
+            }
+        }
+
+        // BOM: иначе Excel открывает UTF-8 как ANSI и кириллица ломается
+        let mut out = String::from('\u{feff}');
+        for row in rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            out.push_str(&cells.join(","));
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let verdict = self.verdict();
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"ru\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(html, "<title>SB|Cheats - отчёт {}</title>", format_timestamp(self.started_at));
+        html.push_str("<style>\
+            body{font-family:sans-serif;background:#1b1b1b;color:#ddd;margin:2em;}\
+            h1,h2{color:#fff;}table{border-collapse:collapse;}\
+            td,th{border:1px solid #444;padding:4px 8px;text-align:left;vertical-align:top;}\
+            .card{border:1px solid #444;border-radius:6px;padding:8px 12px;margin:8px 0;}\
+            .threat{color:#f86449;}.suspicious{color:#f8b449;}.clean{color:#4caf50;}\
+            .muted{color:#888;font-size:small;}.mono{font-family:monospace;}\
+            </style>\n</head>\n<body>\n");
+        html.push_str("<h1>SB|Cheats - отчёт о проверке</h1>\n");
+
+        let class = match verdict {
+            Verdict::Clean => "clean",
+            Verdict::Threats => "threat",
+            Verdict::Incomplete => "suspicious",
+        };
+        let _ = writeln!(html, "<h2 class=\"{}\">{}</h2>", class, escape_html(verdict.label()));
+
+        html.push_str("<table>\n");
+        let mut info = |key: &str, value: &str| {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", key, escape_html(value));
+        };
+        info("Пути", &self.paths.join("\n"));
+        info("Начало", &format_timestamp(self.started_at));
+        info("Длительность", &format!("{:.1} с", self.duration_ms as f64 / 1000.0));
+        info("Версия сканера", &self.scanner_version);
+        info("Версия базы", &self.database_version);
+        info("Источник базы", self.database_source.as_deref().unwrap_or("встроенная"));
+        info("Проверено", &format!("{}/{}", self.stats.checked, self.stats.total));
+        info("Угроз", &self.stats.found.to_string());
+        info("Подозрительных", &self.stats.suspicious.to_string());
+        info("Подавлено", &self.stats.suppressed.to_string());
+        info("Ошибок", &self.errors.len().to_string());
+        if let Some(code) = self.verification_code() {
+            info("Фраза модератора", self.challenge.as_deref().unwrap_or(""));
+            info("Код проверки", &code);
+        }
+        html.push_str("</table>\n");
+
+        let threats: Vec<&ThreatResult> = self.threats.iter()
+            .filter(|t| t.suppressed.is_none() && !t.is_heuristic())
+            .collect();
+        let suspicious: Vec<&ThreatResult> = self.threats.iter()
+            .filter(|t| t.suppressed.is_none() && t.is_heuristic())
+            .collect();
+        let suppressed: Vec<&ThreatResult> = self.threats.iter()
+            .filter(|t| t.suppressed.is_some())
+            .collect();
+
+        for (title, class, list) in [
+            ("Угрозы", "threat", &threats),
+            ("Подозрительные (проверить вручную)", "suspicious", &suspicious),
+            ("Подавлено allowlist", "muted", &suppressed),
+        ] {
+            if list.is_empty() {
+                continue;
+            }
+            let _ = writeln!(html, "<h2>{}: {}</h2>", title, list.len());
+            for threat in list {
+                html_threat_card(&mut html, threat, class);
+            }
+        }
+
+        if !self.jars.is_empty() {
+            let _ = writeln!(html, "<h2>Моды: {}</h2>\n<table>", self.jars.iter().map(|j| j.mods.len()).sum::<usize>());
+            html.push_str("<tr><th>Мод</th><th>Загрузчик</th><th>Название</th><th>Файл</th></tr>\n");
+            for jar in &self.jars {
+                for m in &jar.mods {
+                    let _ = writeln!(html, "<tr><td class=\"mono\">{}</td><td>{}</td><td>{}</td><td class=\"muted\">{}</td></tr>",
+                        escape_html(&mod_title(m)), escape_html(&m.loader),
+                        escape_html(m.name.as_deref().unwrap_or("")), escape_html(&jar.path));
+                }
+            }
+            html.push_str("</table>\n");
+        }
+
+        if !self.errors.is_empty() {
+            let _ = writeln!(html, "<h2>Ошибки: {}</h2>", self.errors.len());
+            for error in &self.errors {
+                let _ = writeln!(html,
+                    "<div class=\"card\"><b>{}</b><br>{}<br><span class=\"muted\">{}</span></div>",
+                    escape_html(error.kind.label()), escape_html(&error.message), escape_html(&error.path));
+            }
+        }
+
+        // Машиночитаемая копия для проверки подписи. '<' внутри JSON бывает только в строках,
+        // а \u003c там равнозначен - так данные не закроют тег script
+        let json = serde_json::to_string(self).unwrap_or_default().replace('<', "\\u003c");
+        let _ = writeln!(html, "{}{}</script>", HTML_REPORT_START, json);
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn html_threat_card(html: &mut String, threat: &ThreatResult, class: &str) {
+    let _ = writeln!(html, "<div class=\"card\">\n<b class=\"{}\">{}</b><br>", class, escape_html(&threat.name));
+    let _ = writeln!(html, "{}: {} (уверенность {}%)<br>",
+        escape_html(threat.kind.label()), escape_html(&threat.cheat_type), threat.confidence);
+    if let Some(ref reason) = threat.suppressed {
+        let _ = writeln!(html, "Причина: {}<br>", escape_html(reason));
+    }
+    let mut lines = threat.details.clone();
+    lines.extend(threat.metadata.summary());
+    if threat.matches.len() > 1 {
+        let others: Vec<String> = threat.matches[1..].iter()
+            .map(|m| format!("{} ({}%)", m.cheat_type, m.confidence))
+            .collect();
+        lines.push(format!("Другие кандидаты: {}", others.join(", ")));
+    }
+    if !lines.is_empty() {
+        html.push_str("<ul>\n");
+        for line in lines {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(&line));
+        }
+        html.push_str("</ul>\n");
+    }
+    let _ = writeln!(html, "<span class=\"muted\">{}</span><br>", escape_html(&threat.path));
+    if !threat.hashes.is_empty() {
+        let _ = writeln!(html, "<span class=\"muted mono\">SHA-256: {}</span>", escape_html(&threat.hashes.sha256));
+    }
+    html.push_str("</div>\n");
+}
+
+// "freecam 1.2.0"
+fn mod_title(m: &ModMetadata) -> String {
+    match m.version {
+        Some(ref version) => format!("{} {}", m.id, version),
+        None => m.id.clone(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            '\n' => out.push_str("<br>"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Имена файлов задаёт игрок: "=cmd|..." не должно стать формулой в Excel.
+// Табуляция и \r в начале тоже: Excel пропускает их перед формулой
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+// "2026-01-31 12:00:00 UTC" без внешних зависимостей
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    // Алгоритм days_from_civil в обратную сторону (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detector::FindingKind;
+    use crate::errors::ScanErrorKind;
+
+    fn report_with(name: &str, details: &str) -> ScanReport {
+        let mut report = ScanReport::new(&CheatDetector::new(), &[PathBuf::from("mods")], UNIX_EPOCH);
+        report.threats.push(ThreatResult {
+            kind: FindingKind::Signature,
+            path: format!("mods/{}", name),
+            name: name.to_string(),
+            size: 0,
+            cheat_type: "Freecam".to_string(),
+            details: vec![details.to_string()],
+            match_score: 1,
+            confidence: 100,
+            exact_hash: None,
+            hashes: Default::default(),
+            suppressed: None,
+            metadata: Default::default(),
+            matches: Vec::new(),
+        });
+        report.errors.push(ScanError::new(ScanErrorKind::Io, "mods/<b>.jar", "нет доступа"));
+        report
+    }
+
+    #[test]
+    fn csv_fields_cannot_become_formulas() {
+        for (value, escaped) in [
+            ("=cmd|' /C calc'!A0", "'=cmd|' /C calc'!A0"),
+            ("+1", "'+1"),
+            ("-1", "'-1"),
+            ("@SUM(A1)", "'@SUM(A1)"),
+            ("\t=1", "'\t=1"),
+            ("a,b", "\"a,b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("=a,b", "\"'=a,b\""),
+            ("freecam.jar", "freecam.jar"),
+        ] {
+            assert_eq!(csv_field(value), escaped, "{}", value);
+        }
+    }
+
+    #[test]
+    fn csv_report_escapes_player_controlled_names() {
+        let csv = report_with("=HYPERLINK(\"http://x\").jar", "a\nb").render(ReportFormat::Csv).unwrap();
+        assert!(csv.starts_with('\u{feff}'));
+        assert!(csv.contains(",\"'=HYPERLINK(\"\"http://x\"\").jar\","));
+        assert!(csv.contains(",\"a\nb\"\r\n"));
+    }
+
+    #[test]
+    fn html_report_escapes_player_controlled_names() {
+        let html = report_with("<script>alert('x')</script>.jar", "</script><img src=x>").render(ReportFormat::Html).unwrap();
+        assert!(!html.contains("<script>alert"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;.jar"));
+        assert!(html.contains("mods/&lt;b&gt;.jar"));
+        // Встроенный JSON не закрывает свой тег script
+        let embedded = &html[html.find(HTML_REPORT_START).unwrap() + HTML_REPORT_START.len()..];
+        assert_eq!(embedded.matches("</script>").count(), 1);
+        assert!(embedded.contains("\\u003c/script>\\u003cimg"));
+    }
+
+    #[test]
+    fn timestamps_in_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_709_164_800), "2024-02-29 00:00:00 UTC");
+        assert_eq!(format_timestamp(1_735_689_599), "2024-12-31 23:59:59 UTC");
+        assert_eq!(format_timestamp(4_102_444_799), "2099-12-31 23:59:59 UTC");
+    }
+}
@@ -0,0 +1,410 @@
+// rules.rs - Логические выражения сигнатур
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use crate::classfile::internal_name;
+use crate::detector::{normalize_entry, JarContext};
+
+// Условие сигнатуры в виде дерева.
+// В JSON: {"all": [{"directory": "net/java/"}, {"class": "i.class"}]}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    All(Vec<Rule>),
+    Any(Vec<Rule>),
+    Not(Box<Rule>),
+    // "N из M"
+    AtLeast { n: usize, of: Vec<Rule> },
+    // Префикс пакета от корня архива: "net/java/" совпадает с "net/java/i.class",
+    // но не с "org/net/java/..." и не с "net/javax/..."
+    Directory(String),
+    // Без "/" - точное имя файла ("i.class" не совпадает с "gui.class"),
+    // с "/" - точный хвост пути по сегментам ("hb/Modules/Hitbox.class")
+    Class(String),
+    // Glob по нормализованному пути записи: "net/*/i.class", "**/Hitbox*.class"
+    PathGlob(PathGlob),
+    // Регулярное выражение по нормализованному пути записи
+    PathRegex(PathRegex),
+    Size {
+        kb: Vec<f32>,
+        #[serde(default = "default_tolerance")]
+        tolerance: f32,
+    },
+    // SHA-256 / SHA-1 (hex) или murmur2 (десятичный) файла
+    Hash(String),
+    // id мода из метаданных загрузчика
+    ModId(String),
+    // Constant pool классов (переживает переименование пакетов):
+    // строковый литерал целиком, с учётом регистра
+    String(String),
+    // Подстрока любой CONSTANT_Utf8 без учёта регистра
+    Utf8Contains(String),
+    // Ссылка на класс: "net/minecraft/class_2824" или "net.minecraft.class_2824"
+    ClassRef(String),
+    MethodRef { owner: String, name: String },
+    FieldRef { owner: String, name: String },
+    // Метаданные jar: mod_id, mod_name, mod_version, author, environment, entrypoint
+    // или атрибут MANIFEST.MF; без value - достаточно наличия ключа
+    Metadata {
+        key: String,
+        #[serde(default)]
+        value: Option<String>,
+    },
+}
+
+fn default_tolerance() -> f32 {
+    0.05
+}
+
+// Шаблоны компилируются один раз при загрузке базы
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PathGlob {
+    raw: String,
+    matcher: GlobMatcher,
+}
+
+impl TryFrom<String> for PathGlob {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let matcher = GlobBuilder::new(&normalize_entry(&raw))
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| format!("glob \"{}\": {}", raw, e))?
+            .compile_matcher();
+        Ok(Self { raw, matcher })
+    }
+}
+
+impl From<PathGlob> for String {
+    fn from(glob: PathGlob) -> Self {
+        glob.raw
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PathRegex {
+    raw: String,
+    regex: Regex,
+}
+
+impl TryFrom<String> for PathRegex {
+    type Error = String;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        let regex = RegexBuilder::new(&raw)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("regex \"{}\": {}", raw, e))?;
+        Ok(Self { raw, regex })
+    }
+}
+
+impl From<PathRegex> for String {
+    fn from(regex: PathRegex) -> Self {
+        regex.raw
+    }
+}
+
+fn class_matches(entry: &str, class: &str) -> bool {
+    if class.contains('/') {
+        entry == class
+            || entry.strip_suffix(class).is_some_and(|head| head.ends_with('/'))
+    } else {
+        entry.rsplit('/').next() == Some(class)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub matched: bool,
+    pub score: usize,
+    pub hits: Vec<String>,
+}
+
+impl Rule {
+    pub fn evaluate(&self, ctx: &JarContext) -> Evaluation {
+        let mut hits = Vec::new();
+        let (matched, score) = self.eval(ctx, &mut hits);
+        Evaluation { matched, score, hits }
+    }
+
+    // Нужен ли разбор .class файлов для этого правила
+    pub fn uses_constants(&self) -> bool {
+        match self {
+            Rule::All(rules) | Rule::Any(rules) | Rule::AtLeast { of: rules, .. } => {
+                rules.iter().any(Rule::uses_constants)
+            }
+            Rule::Not(rule) => rule.uses_constants(),
+            Rule::String(_) | Rule::Utf8Contains(_) | Rule::ClassRef(_)
+            | Rule::MethodRef { .. } | Rule::FieldRef { .. } => true,
+            _ => false,
+        }
+    }
+
+    // Максимально возможный score - для "Совпадений: x/y"
+    pub fn max_score(&self) -> usize {
+        match self {
+            Rule::All(rules) | Rule::AtLeast { of: rules, .. } => {
+                rules.iter().map(Rule::max_score).sum()
+            }
+            Rule::Any(rules) => rules.iter().map(Rule::max_score).max().unwrap_or(0),
+            Rule::Not(_) => 0,
+            _ => 1,
+        }
+    }
+
+    // Дочерние условия вычисляются целиком (без short-circuit), чтобы score и hits
+    // отражали все совпадения, а не только первое
+    fn eval(&self, ctx: &JarContext, hits: &mut Vec<String>) -> (bool, usize) {
+        match self {
+            Rule::All(rules) => {
+                let mut all = true;
+                let mut score = 0;
+                for rule in rules {
+                    let (m, s) = rule.eval(ctx, hits);
+                    all &= m;
+                    score += s;
+                }
+                (all, score)
+            }
+            Rule::Any(rules) => {
+                let mut any = false;
+                let mut score = 0;
+                for rule in rules {
+                    let (m, s) = rule.eval(ctx, hits);
+                    any |= m;
+                    score = score.max(s);
+                }
+                (any, score)
+            }
+            Rule::Not(rule) => {
+                // Совпадения внутри отрицания не учитываются
+                let (m, _) = rule.eval(ctx, &mut Vec::new());
+                (!m, 0)
+            }
+            Rule::AtLeast { n, of } => {
+                let mut count = 0;
+                let mut score = 0;
+                for rule in of {
+                    let (m, s) = rule.eval(ctx, hits);
+                    if m {
+                        count += 1;
+                    }
+                    score += s;
+                }
+                (count >= *n, score)
+            }
+            leaf => {
+                let hit = leaf.eval_leaf(ctx);
+                if let Some(hit) = hit {
+                    hits.push(hit);
+                    (true, 1)
+                } else {
+                    (false, 0)
+                }
+            }
+        }
+    }
+
+    fn eval_leaf(&self, ctx: &JarContext) -> Option<String> {
+        match self {
+            Rule::Directory(dir) => {
+                let mut prefix = normalize_entry(dir);
+                if !prefix.ends_with('/') {
+                    prefix.push('/');
+                }
+                ctx.entries.iter()
+                    .any(|fp| fp.starts_with(&prefix))
+                    .then(|| format!("Директория: {}", dir))
+            }
+            Rule::Class(class) => {
+                let class_norm = normalize_entry(class);
+                ctx.entries.iter()
+                    .find(|fp| class_matches(fp, &class_norm))
+                    .map(|fp| format!("Класс: {}", fp))
+            }
+            Rule::PathGlob(glob) => {
+                ctx.entries.iter()
+                    .find(|fp| glob.matcher.is_match(fp.as_str()))
+                    .map(|fp| format!("Путь: {} ({})", fp, glob.raw))
+            }
+            Rule::PathRegex(regex) => {
+                ctx.entries.iter()
+                    .find(|fp| regex.regex.is_match(fp))
+                    .map(|fp| format!("Путь: {} ({})", fp, regex.raw))
+            }
+            Rule::Size { kb, tolerance } => {
+                let size_kb = ctx.size_kb();
+                kb.iter()
+                    .find(|&&target| {
+                        let min_size = target * (1.0 - tolerance);
+                        let max_size = target * (1.0 + tolerance);
+                        size_kb >= min_size && size_kb <= max_size
+                    })
+                    .map(|target| format!("Размер: ~{:.0} KB", target))
+            }
+            Rule::Hash(hash) => ctx.hashes.find(hash),
+            Rule::ModId(id) => {
+                ctx.metadata.has_mod_id(id).then(|| format!("Mod id: {}", id))
+            }
+            Rule::String(literal) => {
+                ctx.constants.strings.get(literal)
+                    .map(|class| format!("Строка \"{}\" в {}", literal, class))
+            }
+            Rule::Utf8Contains(needle) => {
+                let needle = needle.to_lowercase();
+                ctx.constants.utf8.iter()
+                    .find(|(value, _)| value.to_lowercase().contains(&needle))
+                    .map(|(value, class)| format!("Utf8 \"{}\" в {}", value, class))
+            }
+            Rule::ClassRef(name) => {
+                let name = internal_name(name);
+                ctx.constants.class_refs.get(&name)
+                    .map(|class| format!("Ссылка на {} в {}", name, class))
+            }
+            Rule::MethodRef { owner, name } => {
+                let key = format!("{}.{}", internal_name(owner), name);
+                ctx.constants.method_refs.get(&key)
+                    .map(|class| format!("Вызов {} в {}", key, class))
+            }
+            Rule::FieldRef { owner, name } => {
+                let key = format!("{}.{}", internal_name(owner), name);
+                ctx.constants.field_refs.get(&key)
+                    .map(|class| format!("Поле {} в {}", key, class))
+            }
+            Rule::Metadata { key, value } => {
+                ctx.metadata.values(key).into_iter()
+                    .find(|actual| value.as_ref().is_none_or(|v| actual.eq_ignore_ascii_case(v)))
+                    .map(|actual| format!("{}: {}", key, actual))
+            }
+            Rule::All(_) | Rule::Any(_) | Rule::Not(_) | Rule::AtLeast { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::{Duration, Instant};
+    use zip::ZipArchive;
+    use super::*;
+    use crate::classfile::ConstantIndex;
+    use crate::detector::CheatInfo;
+    use crate::testutil::{jar, ClassBuilder};
+
+    fn rule(json: &str) -> Rule {
+        serde_json::from_str(json).unwrap()
+    }
+
+    // Чит после переименования пакетов: пути другие, constant pool тот же
+    fn renamed_cheat() -> JarContext {
+        let mut class = ClassBuilder::new("a/b/C");
+        class.string("Killaura");
+        class.class("net/minecraft/class_2824");
+        class.method_ref("net/minecraft/class_634", "method_2883", "(Lnet/minecraft/class_2596;)V");
+        class.field_ref("net/minecraft/class_310", "field_1724", "Lnet/minecraft/class_746;");
+        let data = jar(&[("a/b/C.class", &class.build())]);
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        JarContext {
+            entries: vec!["a/b/c.class".to_string()],
+            constants: ConstantIndex::build(&mut archive, Instant::now() + Duration::from_secs(30)),
+            ..JarContext::default()
+        }
+    }
+
+    #[test]
+    fn constant_pool_rules_survive_renamed_packages() {
+        let signature = rule(r#"{"all": [
+            {"string": "Killaura"},
+            {"class_ref": "net.minecraft.class_2824"},
+            {"method_ref": {"owner": "net/minecraft/class_634", "name": "method_2883"}},
+            {"field_ref": {"owner": "net.minecraft.class_310", "name": "field_1724"}}
+        ]}"#);
+        let evaluation = signature.evaluate(&renamed_cheat());
+        assert!(evaluation.matched);
+        assert_eq!(evaluation.score, 4);
+        assert_eq!(evaluation.hits[0], "Строка \"Killaura\" в a/b/C");
+        assert_eq!(evaluation.hits[2], "Вызов net/minecraft/class_634.method_2883 в a/b/C");
+    }
+
+    #[test]
+    fn string_is_exact_and_utf8_is_case_insensitive() {
+        let ctx = renamed_cheat();
+        assert!(!rule(r#"{"string": "killaura"}"#).evaluate(&ctx).matched);
+        assert!(!rule(r#"{"string": "Kill"}"#).evaluate(&ctx).matched);
+        assert!(rule(r#"{"utf8_contains": "KILLAURA"}"#).evaluate(&ctx).matched);
+        assert!(!rule(r#"{"method_ref": {"owner": "net/minecraft/class_634", "name": "method_1"}}"#).evaluate(&ctx).matched);
+    }
+
+    #[test]
+    fn only_constant_rules_need_class_parsing() {
+        assert!(rule(r#"{"any": [{"directory": "net/java/"}, {"not": {"class_ref": "a/B"}}]}"#).uses_constants());
+        assert!(!rule(r#"{"all": [{"directory": "net/java/"}, {"class": "i.class"}]}"#).uses_constants());
+    }
+
+    fn entries(names: &[&str]) -> JarContext {
+        JarContext {
+            entries: names.iter().map(|n| normalize_entry(n)).collect(),
+            ..JarContext::default()
+        }
+    }
+
+    #[test]
+    fn at_least_counts_matched_children() {
+        let signature = rule(r#"{"at_least": {"n": 2, "of": [
+            {"directory": "me/bushroot/hb/"}, {"class": "Hitbox.class"}, {"class": "Reach.class"}
+        ]}}"#);
+        assert_eq!(signature.max_score(), 3);
+        assert!(!signature.evaluate(&entries(&["me/bushroot/hb/Main.class"])).matched);
+
+        let evaluation = signature.evaluate(&entries(&["me/bushroot/hb/Modules/Hitbox.class"]));
+        assert!(evaluation.matched);
+        assert_eq!(evaluation.score, 2);
+        assert_eq!(evaluation.hits, ["Директория: me/bushroot/hb/", "Класс: me/bushroot/hb/modules/hitbox.class"]);
+    }
+
+    #[test]
+    fn not_excludes_without_scoring() {
+        let signature = rule(r#"{"all": [
+            {"directory": "net/java/"}, {"class": "i.class"},
+            {"not": {"any": [{"directory": "net/minecraft/"}, {"directory": "org/apache/"}]}}
+        ]}"#);
+        let cheat = signature.evaluate(&entries(&["net/java/i.class"]));
+        assert!(cheat.matched);
+        assert_eq!((cheat.score, signature.max_score()), (2, 2));
+        assert!(!signature.evaluate(&entries(&["net/java/i.class", "org/apache/Log.class"])).matched);
+    }
+
+    #[test]
+    fn directory_and_class_match_whole_segments() {
+        let ctx = entries(&["org/net/java/a.class", "net/javax/b.class", "gui.class", "x/hb/Modules/Hitbox.class"]);
+        assert!(!rule(r#"{"directory": "net/java"}"#).evaluate(&ctx).matched);
+        assert!(!rule(r#"{"class": "i.class"}"#).evaluate(&ctx).matched);
+        assert!(rule(r#"{"class": "hb/Modules/Hitbox.class"}"#).evaluate(&ctx).matched);
+        assert!(!rule(r#"{"class": "b/Modules/Hitbox.class"}"#).evaluate(&ctx).matched);
+        assert!(rule(r#"{"path_glob": "**/Hitbox*.class"}"#).evaluate(&ctx).matched);
+    }
+
+    #[test]
+    fn legacy_fields_honor_min_conditions() {
+        let info = |min_conditions: usize| -> CheatInfo {
+            serde_json::from_value(serde_json::json!({
+                "directories": ["cheat/"],
+                "classes": ["Aura.class"],
+                "sizes_kb": [100.0],
+                "description": "тест",
+                "min_conditions": min_conditions,
+            })).unwrap()
+        };
+        let ctx = entries(&["cheat/Aura.class"]);
+        // Директория + класс = 2 условия; размер не совпал
+        assert!(info(2).legacy_rule().evaluate(&ctx).matched);
+        assert!(!info(3).legacy_rule().evaluate(&ctx).matched);
+        assert!(info(1).legacy_rule().evaluate(&entries(&["cheat/Other.class"])).matched);
+    }
+}
@@ -3,29 +3,76 @@ use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
-use crate::detector::{CheatDetector, ThreatResult};
+use crate::detector::{CheatDetector, JarRecord, ThreatResult};
+use crate::errors::{ScanError, ScanErrorKind};
+use crate::injection::{self, LaunchReference};
+use crate::integrity::LibraryFile;
+use crate::launchers::Instance;
+use crate::logs;
+use crate::versions::{self, VersionInfo};
+use crate::sniff;
+
+// Файлы вне этого диапазона не проверяются
+const MIN_FILE_SIZE: u64 = 1024;
+const MAX_FILE_SIZE: u64 = 500 * 1024 * 1024;
+// Как часто отправлять прогресс и статистику
+const STATS_EVERY: usize = 50;
 
 #[derive(Debug, Clone)]
 pub enum ScanMessage {
     Progress(f32),
-    ThreatFound(ThreatResult),
+    ThreatFound(Box<ThreatResult>),
+    // Проверенный jar с модами, см. JarRecord
+    JarChecked(Box<JarRecord>),
     Stats(ScanStats),
+    Error(ScanError),
+    // Папка или файл вне указанных путей, которые тоже проверялись: versions/, libraries/,
+    // logs/, агенты из аргументов запуска. Попадает в пути отчёта
+    ExtraPath(PathBuf),
     Complete,
-    Error(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScanStats {
     pub total: usize,
     pub checked: usize,
     pub found: usize,
+    pub suspicious: usize,
+    pub suppressed: usize,
+}
+
+// Что проверять: файл из найденных, агент/твикер из аргументов запуска, версия из versions/,
+// библиотека из её json (со сверкой SHA-1), лог или краш-репорт
+#[derive(Debug, Clone)]
+pub enum ScanTarget {
+    File(PathBuf),
+    Launch(LaunchReference),
+    Version(VersionInfo),
+    Library(LibraryFile),
+    Log(PathBuf),
+}
+
+impl ScanTarget {
+    fn path(&self) -> Option<&Path> {
+        match self {
+            ScanTarget::File(path) => Some(path),
+            ScanTarget::Launch(reference) => reference.resolved.as_deref(),
+            ScanTarget::Version(version) => version.jar_path.as_deref(),
+            ScanTarget::Library(library) => Some(library.path.as_path()),
+            ScanTarget::Log(path) => Some(path),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Scanner {
     detector: CheatDetector,
     cancel_flag: Arc<AtomicBool>,
+    // Инстансы, чьи аргументы запуска, версии и логи проверяются вместе с папками,
+    // если к ним относится один из путей проверки
+    launch_instances: Option<Vec<Instance>>,
 }
 
 impl Scanner {
@@ -33,104 +80,437 @@ impl Scanner {
         Self {
             detector,
             cancel_flag: Arc::new(AtomicBool::new(false)),
+            launch_instances: None,
         }
     }
 
+    // Плюс -javaagent / --tweakClass из окружения и профилей этих инстансов
+    // и версии из их versions/, логи и краш-репорты (см. injection, versions, logs).
+    // Инстанс проверяется, только если путь проверки лежит в его папке игры или содержит её:
+    // остальные инстансы на машине к проверке не относятся
+    pub fn with_launch_analysis(mut self, instances: Vec<Instance>) -> Self {
+        self.launch_instances = Some(instances);
+        self
+    }
+
+    pub fn detector(&self) -> &CheatDetector {
+        &self.detector
+    }
+
+    // Клоны Scanner разделяют флаг: отмена из GUI останавливает рабочий поток
     pub fn cancel(&self) {
         self.cancel_flag.store(true, Ordering::Relaxed);
     }
 
-    pub fn find_jar_files(&self, search_path: &Path) -> Vec<PathBuf> {
-        WalkDir::new(search_path)
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_flag.load(Ordering::Relaxed)
+    }
+
+    // Ошибки обхода и чтения не теряются: непрочитанный файл может быть читом
+    pub fn find_archive_files(&self, search_path: &Path) -> (Vec<PathBuf>, Vec<ScanError>) {
+        let found: Vec<Result<PathBuf, ScanError>> = WalkDir::new(search_path)
             .follow_links(true)
             .into_iter()
-            .filter_map(|e| e.ok())
             .par_bridge() // Параллельная обработка
             .filter_map(|entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => return Some(Err(walk_error(search_path, &e))),
+                };
                 let path = entry.path();
-
-                // FAST PATH: проверка расширения
-                if !path.extension()
-                    .and_then(|s| s.to_str())
-                    .map(|ext| ext.eq_ignore_ascii_case("jar"))
-                    .unwrap_or(false) {
+                if !entry.file_type().is_file() {
                     return None;
                 }
 
                 // FAST PATH: проверка размера
-                std::fs::metadata(path).ok()
-                    .filter(|m| {
-                        let size = m.len();
-                        size >= 1024 && size <= 500 * 1024 * 1024
-                    })
-                    .map(|_| path.to_path_buf())
+                let size = match std::fs::metadata(path) {
+                    Ok(metadata) => metadata.len(),
+                    Err(e) => return Some(Err(ScanError::from_io(path, &e))),
+                };
+                if size < MIN_FILE_SIZE {
+                    return None;
+                }
+
+                // FAST PATH: .jar берём без чтения, остальное - только если внутри ZIP
+                // (переименованные .jar.disabled, .png, .dat, без расширения, дописанные архивы)
+                let is_jar = path.extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| e.eq_ignore_ascii_case("jar"));
+                let is_archive = is_jar || match sniff::is_archive_file(path) {
+                    Ok(is_archive) => is_archive,
+                    Err(e) => return Some(Err(ScanError::from_io(path, &e))),
+                };
+                if !is_archive {
+                    return None;
+                }
+                // Раздутый до гигабайта jar не проверяется, но и не пропадает молча
+                if size > MAX_FILE_SIZE {
+                    return Some(Err(ScanError::new(
+                        ScanErrorKind::LimitExceeded,
+                        path.display().to_string(),
+                        format!("Архив {} MB больше лимита {} MB, не проверен",
+                            size / 1024 / 1024, MAX_FILE_SIZE / 1024 / 1024),
+                    )));
+                }
+                Some(Ok(path.to_path_buf()))
             })
-            .collect()
+            .collect();
+
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        for item in found {
+            match item {
+                Ok(file) => files.push(file),
+                Err(error) => errors.push(error),
+            }
+        }
+        (files, errors)
     }
 
-    // ОПТИМИЗАЦИЯ: Батч-сканирование с минимальными логами
+    // ОПТИМИЗАЦИЯ: Батч-сканирование с минимальными логами.
+    // После отмены оставшиеся файлы пропускаются, checked < total
     pub fn scan_files(
         &self,
-        jar_files: Vec<PathBuf>,
-        sender: Sender<ScanMessage>,
+        targets: Vec<ScanTarget>,
+        sender: &Sender<ScanMessage>,
         num_threads: usize,
-    ) -> Result<(), String> {
-        if jar_files.is_empty() {
-            return Ok(());
-        }
-
+    ) -> Result<ScanStats, String> {
         // Настройка пула потоков
-        rayon::ThreadPoolBuilder::new()
+        let pool = rayon::ThreadPoolBuilder::new()
             .num_threads(num_threads)
             .build()
-            .map_err(|e| format!("Thread pool error: {}", e))?
-            .install(|| {
-                let total = jar_files.len();
-                let checked = Arc::new(AtomicUsize::new(0));
-                let found = Arc::new(AtomicUsize::new(0));
-
-                // КРИТИЧЕСКАЯ ОПТИМИЗАЦИЯ: Батч-обработка без логов на каждый файл
-                jar_files.par_iter()
-                    .try_for_each(|jar_path| {
-                        // Проверка отмены
-                        if self.cancel_flag.load(Ordering::Relaxed) {
-                            return Err("Cancelled");
-                        }
-
-                        // Сканируем файл
-                        if let Some(threat) = self.detector.check_jar_file(jar_path) {
-                            found.fetch_add(1, Ordering::Relaxed);
-                            sender.send(ScanMessage::ThreatFound(threat))
-                                .map_err(|_| "Channel closed")?;
-                        }
-
-                        let current = checked.fetch_add(1, Ordering::Relaxed) + 1;
-
-                        // ОПТИМИЗАЦИЯ: Обновляем статистику каждые 50 файлов
-                        if current % 50 == 0 || current == total {
-                            let progress = current as f32 / total as f32;
-                            sender.send(ScanMessage::Progress(progress))
-                                .map_err(|_| "Channel closed")?;
-
-                            sender.send(ScanMessage::Stats(ScanStats {
-                                total,
-                                checked: current,
-                                found: found.load(Ordering::Relaxed),
-                            })).map_err(|_| "Channel closed")?;
-                        }
-
-                        Ok::<_, &str>(())
-                    })
-                    .map_err(|e| e.to_string())?;
-
-                // Финальная статистика
-                sender.send(ScanMessage::Stats(ScanStats {
-                    total,
-                    checked: checked.load(Ordering::Relaxed),
-                    found: found.load(Ordering::Relaxed),
-                })).map_err(|_| "Channel closed".to_string())?;
-
-                Ok(())
+            .map_err(|e| format!("Thread pool error: {}", e))?;
+
+        let total = targets.len();
+        let checked = AtomicUsize::new(0);
+        let found = AtomicUsize::new(0);
+        let suspicious = AtomicUsize::new(0);
+        let suppressed = AtomicUsize::new(0);
+        let stats = || ScanStats {
+            total,
+            checked: checked.load(Ordering::Relaxed),
+            found: found.load(Ordering::Relaxed),
+            suspicious: suspicious.load(Ordering::Relaxed),
+            suppressed: suppressed.load(Ordering::Relaxed),
+        };
+
+        pool.install(|| {
+            targets.par_iter().for_each(|target| {
+                // Проверка отмены
+                if self.is_cancelled() {
+                    return;
+                }
+
+                let scan = match target {
+                    ScanTarget::File(path) => self.detector.check_jar_file(path),
+                    ScanTarget::Launch(reference) => self.detector.check_launch(reference),
+                    ScanTarget::Version(version) => self.detector.check_version(version),
+                    ScanTarget::Library(library) => self.detector.check_library(library),
+                    ScanTarget::Log(path) => self.detector.check_log(path),
+                };
+                for error in scan.errors {
+                    sender.send(ScanMessage::Error(error)).ok();
+                }
+                for jar in scan.jars {
+                    sender.send(ScanMessage::JarChecked(Box::new(jar))).ok();
+                }
+                for threat in scan.threats {
+                    let counter = if threat.suppressed.is_some() {
+                        &suppressed
+                    } else if threat.is_heuristic() {
+                        &suspicious
+                    } else {
+                        &found
+                    };
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    sender.send(ScanMessage::ThreatFound(Box::new(threat))).ok();
+                }
+
+                let current = checked.fetch_add(1, Ordering::Relaxed) + 1;
+
+                // ОПТИМИЗАЦИЯ: Обновляем статистику каждые 50 файлов
+                if current.is_multiple_of(STATS_EVERY) || current == total {
+                    sender.send(ScanMessage::Progress(current as f32 / total as f32)).ok();
+                    sender.send(ScanMessage::Stats(stats())).ok();
+                }
+            });
+        });
+
+        // Финальная статистика
+        let final_stats = stats();
+        sender.send(ScanMessage::Stats(final_stats.clone())).ok();
+        Ok(final_stats)
+    }
+
+    // Полный проход для GUI и CLI: поиск по всем путям, проверка, Complete в конце
+    pub fn scan_paths(&self, search_paths: &[PathBuf], sender: Sender<ScanMessage>, num_threads: usize) {
+        let mut files = Vec::new();
+        for search_path in search_paths {
+            let (found, errors) = self.find_archive_files(search_path);
+            files.extend(found);
+            for error in errors {
+                sender.send(ScanMessage::Error(error)).ok();
+            }
+        }
+        // Один и тот же файл через пересекающиеся пути проверяется один раз
+        files.sort();
+        files.dedup();
+
+        let targets = self.launch_targets(search_paths, files, &sender);
+
+        sender.send(ScanMessage::Stats(ScanStats {
+            total: targets.len(),
+            ..ScanStats::default()
+        })).ok();
+
+        if let Err(e) = self.scan_files(targets, &sender, num_threads) {
+            let paths: Vec<String> = search_paths.iter().map(|p| p.display().to_string()).collect();
+            sender.send(ScanMessage::Error(ScanError::new(ScanErrorKind::Io, paths.join(", "), e))).ok();
+        }
+        sender.send(ScanMessage::Complete).ok();
+    }
+}
+
+impl Scanner {
+    // Файлы + агенты/твикеры + версии. Файл, уже попавший в проверку как агент
+    // или jar версии, второй раз как обычный не проверяется
+    fn launch_targets(&self, search_paths: &[PathBuf], mut files: Vec<PathBuf>, sender: &Sender<ScanMessage>) -> Vec<ScanTarget> {
+        let Some(instances) = self.launch_instances.as_deref() else {
+            return files.into_iter().map(ScanTarget::File).collect();
+        };
+        let instances: Vec<Instance> = instances.iter()
+            .filter(|instance| in_scope(search_paths, &instance.game_dir))
+            .cloned()
+            .collect();
+
+        let mut special: Vec<ScanTarget> = Vec::new();
+        let mut extra: Vec<PathBuf> = Vec::new();
+        for reference in injection::collect(&instances) {
+            let duplicate = reference.resolved.is_some() && special.iter()
+                .any(|t| t.path() == reference.resolved.as_deref());
+            if !duplicate {
+                extra.extend(reference.resolved.clone());
+                special.push(ScanTarget::Launch(reference));
+            }
+        }
+        let mut game_dirs: Vec<&Path> = instances.iter().map(|i| i.game_dir.as_path()).collect();
+        game_dirs.sort();
+        game_dirs.dedup();
+        let mut libraries: Vec<LibraryFile> = Vec::new();
+        for game_dir in game_dirs {
+            let (found, errors) = versions::collect(game_dir);
+            for error in errors {
+                sender.send(ScanMessage::Error(error)).ok();
+            }
+            for version in found {
+                extra.push(game_dir.join("versions"));
+                // Неизвестные библиотеки проверяются как обычные jar
+                extra.extend(version.unknown_library_jars.iter().map(|jar| libraries_root(jar)));
+                files.extend(version.unknown_library_jars.iter().cloned());
+                libraries.extend(version.libraries.iter().cloned());
+                special.push(ScanTarget::Version(version));
+            }
+            for log in logs::collect(game_dir) {
+                extra.extend(log.parent().map(Path::to_path_buf));
+                special.push(ScanTarget::Log(log));
+            }
+        }
+        // Одну библиотеку обычно перечисляют несколько версий
+        libraries.sort_by(|a, b| a.path.cmp(&b.path));
+        libraries.dedup_by(|a, b| a.path == b.path);
+        for library in libraries {
+            if !special.iter().any(|t| t.path() == Some(library.path.as_path())) {
+                extra.push(libraries_root(&library.path));
+                special.push(ScanTarget::Library(library));
+            }
+        }
+
+        // После сортировки папка идёт раньше своего содержимого
+        extra.sort();
+        let mut reported: Vec<PathBuf> = Vec::new();
+        for path in extra {
+            let covered = reported.iter().any(|p| path.starts_with(p))
+                || search_paths.iter().any(|p| canonical(&path).starts_with(canonical(p)));
+            if !covered {
+                sender.send(ScanMessage::ExtraPath(path.clone())).ok();
+                reported.push(path);
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        files.retain(|file| !special.iter().any(|t| t.path() == Some(file.as_path())));
+        files.into_iter().map(ScanTarget::File).chain(special).collect()
+    }
+}
+
+// Путь проверки внутри папки игры (mods/) или папка игры внутри пути проверки
+fn in_scope(search_paths: &[PathBuf], game_dir: &Path) -> bool {
+    let game_dir = canonical(game_dir);
+    search_paths.iter()
+        .map(|p| canonical(p))
+        .any(|p| p.starts_with(&game_dir) || game_dir.starts_with(&p))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// В отчёт - папка libraries/, а не каждая библиотека из неё
+fn libraries_root(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|p| p.file_name().is_some_and(|name| name == "libraries"))
+        .unwrap_or(path)
+        .to_path_buf()
+}
+
+fn walk_error(root: &Path, error: &walkdir::Error) -> ScanError {
+    let path = error.path().unwrap_or(root);
+    match error.io_error() {
+        Some(io_error) => ScanError::from_io(path, io_error),
+        // Например, цикл символических ссылок
+        None => ScanError::new(ScanErrorKind::Io, path.display().to_string(), error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::SystemTime;
+    use crate::launchers::Launcher;
+    use crate::report::{ScanReport, Verdict};
+    use crate::testutil::jar;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Все сообщения проверки, собранные в отчёт, как это делают GUI и CLI
+    fn scan(paths: &[PathBuf]) -> ScanReport {
+        let detector = CheatDetector::new();
+        let mut report = ScanReport::new(&detector, paths, SystemTime::now());
+        let (sender, receiver) = mpsc::channel();
+        Scanner::new(detector).scan_paths(paths, sender, 2);
+        for msg in receiver {
+            report.record(&msg);
+        }
+        report
+    }
+
+    #[test]
+    fn corrupt_jar_makes_scan_incomplete() {
+        let dir = temp_dir("corrupt-jar");
+        std::fs::write(dir.join("broken.jar"), vec![0u8; 4096]).unwrap();
+        let report = scan(std::slice::from_ref(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((report.stats.total, report.stats.checked), (1, 1));
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].kind, ScanErrorKind::CorruptArchive);
+        assert!(report.errors[0].path.ends_with("broken.jar"));
+        assert_eq!(report.verdict(), Verdict::Incomplete);
+    }
+
+    #[test]
+    fn unreadable_path_makes_scan_incomplete() {
+        let missing = temp_dir("unreadable").join("no-such-dir");
+        let report = scan(std::slice::from_ref(&missing));
+        std::fs::remove_dir_all(missing.parent().unwrap()).unwrap();
+
+        assert_eq!(report.stats.total, 0);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].kind, ScanErrorKind::Io);
+        assert_eq!(report.errors[0].path, missing.display().to_string());
+        assert_eq!(report.verdict(), Verdict::Incomplete);
+    }
+
+    // Несжимаемые данные: архив не меньше MIN_FILE_SIZE
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state: u32 = 12345;
+        (0..len).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        }).collect()
+    }
+
+    fn messages(scanner: &Scanner, paths: &[PathBuf]) -> Vec<ScanMessage> {
+        let (sender, receiver) = mpsc::channel();
+        scanner.scan_paths(paths, sender, 2);
+        receiver.into_iter().collect()
+    }
+
+    fn names(files: &[PathBuf]) -> Vec<String> {
+        let mut names: Vec<String> = files.iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn finds_archives_by_extension_and_content() {
+        let dir = temp_dir("find-archives");
+        let archive = jar(&[("a.bin", &noise(2048))]);
+        std::fs::write(dir.join("mod.jar"), noise(2048)).unwrap();
+        std::fs::write(dir.join("tiny.jar"), b"PK").unwrap();
+        std::fs::write(dir.join("skin.png"), &archive).unwrap();
+        std::fs::write(dir.join("cache"), &archive).unwrap();
+        std::fs::write(dir.join("notes.txt"), noise(2048)).unwrap();
+        let (files, errors) = Scanner::new(CheatDetector::new()).find_archive_files(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(errors.is_empty());
+        assert_eq!(names(&files), ["cache", "mod.jar", "skin.png"]);
+    }
+
+    #[test]
+    fn overlapping_paths_check_file_once() {
+        let dir = temp_dir("overlapping");
+        std::fs::create_dir_all(dir.join("mods")).unwrap();
+        std::fs::write(dir.join("mods/a.jar"), jar(&[("a.bin", &noise(2048))])).unwrap();
+        let messages = messages(&Scanner::new(CheatDetector::new()), &[dir.clone(), dir.join("mods")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let last_stats = messages.iter().rev().find_map(|m| match m {
+            ScanMessage::Stats(stats) => Some(stats.clone()),
+            _ => None,
+        }).unwrap();
+        assert_eq!((last_stats.total, last_stats.checked), (1, 1));
+        assert!(matches!(messages.last(), Some(ScanMessage::Complete)));
+    }
+
+    #[test]
+    fn launch_analysis_only_for_instances_in_scope() {
+        let dir = temp_dir("launch-scope");
+        let instance = |name: &str| {
+            let game_dir = dir.join(name);
+            std::fs::create_dir_all(game_dir.join("mods")).unwrap();
+            std::fs::create_dir_all(game_dir.join("logs")).unwrap();
+            std::fs::write(game_dir.join("logs/latest.log"), "[12:00:00] [main/INFO]: ok\n").unwrap();
+            Instance {
+                launcher: Launcher::Vanilla,
+                name: name.to_string(),
+                game_dir,
+                minecraft_version: None,
+                loader: None,
+                java_args: Vec::new(),
+            }
+        };
+        let scanned = instance("scanned");
+        let other = instance("other");
+        let scanner = Scanner::new(CheatDetector::new()).with_launch_analysis(vec![scanned.clone(), other]);
+        let messages = messages(&scanner, &[scanned.mods_dir()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let extra: Vec<&PathBuf> = messages.iter()
+            .filter_map(|m| match m {
+                ScanMessage::ExtraPath(path) => Some(path),
+                _ => None,
             })
+            .collect();
+        assert_eq!(extra, [&scanned.game_dir.join("logs")]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,333 @@
+// signing.rs - Подпись отчётов Ed25519: правка JSON/HTML после сканирования видна при проверке.
+// Всё офлайн: закрытый ключ выдаёт сервер вместе со сборкой (файл рядом с exe),
+// модератор держит список доверенных открытых ключей.
+// Подпись доказывает только целостность при передаче: ключ лежит на машине игрока,
+// и игрок, достав его, может подписать изменённый отчёт. Что проверка действительно
+// прошла, подтверждает код проверки по фразе модератора (см. challenge)
+use std::path::{Path, PathBuf};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::hashes::to_hex;
+use crate::report::ScanReport;
+
+pub const ALGORITHM: &str = "ed25519";
+const SIGNING_KEY_FILE: &str = "report_signing.key";
+const TRUSTED_KEYS_FILE: &str = "report_trusted.keys";
+// Отчёт внутри HTML - между этими маркерами (см. ScanReport::to_html)
+pub const HTML_REPORT_START: &str = "<script type=\"application/json\" id=\"sbcheats-report\">";
+const HTML_REPORT_END: &str = "</script>";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportSignature {
+    pub algorithm: String,
+    // hex, 32 байта
+    pub public_key: String,
+    // hex, 64 байта; подписан канонический JSON отчёта без поля signature
+    pub signature: String,
+}
+
+#[derive(Clone)]
+pub struct ReportSigner {
+    key: SigningKey,
+}
+
+impl ReportSigner {
+    pub fn generate() -> Result<Self, String> {
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).map_err(|e| format!("Генератор случайных чисел: {}", e))?;
+        Ok(Self { key: SigningKey::from_bytes(&seed) })
+    }
+
+    // Файл ключа: 64 hex-символа (seed), пробелы и переводы строк игнорируются
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let seed: [u8; 32] = from_hex(text.trim())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("{}: ожидается 64 hex-символа", path.display()))?;
+        Ok(Self { key: SigningKey::from_bytes(&seed) })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, format!("{}\n", to_hex(self.key.as_bytes())))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    // Ключ рядом с exe; нет файла - отчёты без подписи
+    pub fn load_default() -> Option<Result<Self, String>> {
+        let path = exe_dir()?.join(SIGNING_KEY_FILE);
+        path.is_file().then(|| Self::from_file(&path))
+    }
+
+    pub fn public_key(&self) -> String {
+        to_hex(self.key.verifying_key().as_bytes())
+    }
+
+    pub fn sign(&self, report: &mut ScanReport) -> Result<(), String> {
+        report.signature = None;
+        let value = serde_json::to_value(&*report).map_err(|e| e.to_string())?;
+        let signature = self.key.sign(&canonical_bytes(&value));
+        report.signature = Some(ReportSignature {
+            algorithm: ALGORITHM.to_string(),
+            public_key: self.public_key(),
+            signature: to_hex(&signature.to_bytes()),
+        });
+        Ok(())
+    }
+}
+
+// Открытые ключи, которым модератор доверяет: по одному hex на строку, # - комментарий
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+    pub keys: Vec<String>,
+}
+
+impl TrustedKeys {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut keys = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if from_hex(line).is_none_or(|bytes| bytes.len() != 32) {
+                return Err(format!("{}:{}: ожидается 64 hex-символа", path.display(), number + 1));
+            }
+            keys.push(line.to_ascii_lowercase());
+        }
+        Ok(Self { keys })
+    }
+
+    pub fn load_default() -> (Self, Option<String>) {
+        let Some(path) = exe_dir().map(|dir| dir.join(TRUSTED_KEYS_FILE)).filter(|p| p.is_file()) else {
+            return (Self::default(), None);
+        };
+        match Self::from_file(&path) {
+            Ok(keys) => (keys, None),
+            Err(e) => (Self::default(), Some(e)),
+        }
+    }
+
+    pub fn contains(&self, public_key: &str) -> bool {
+        self.keys.iter().any(|k| k.eq_ignore_ascii_case(public_key))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    // Подпись верна, ключ доверенный
+    Trusted,
+    // Подпись верна, но ключ неизвестен: отчёт мог подписать кто угодно своим ключом
+    UnknownKey(String),
+    Unsigned,
+    // Содержимое не совпадает с подписью
+    Altered,
+    // Испорченный блок подписи или неизвестный алгоритм
+    Invalid(String),
+}
+
+impl Verification {
+    pub fn is_trusted(&self) -> bool {
+        *self == Verification::Trusted
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Verification::Trusted => "Подпись верна: отчёт не изменён после подписи доверенным ключом".to_string(),
+            Verification::UnknownKey(key) => format!("Подпись верна, но ключ не доверенный: {}", key),
+            Verification::Unsigned => "Отчёт без подписи: подлинность не проверить".to_string(),
+            Verification::Altered => "Отчёт изменён после подписи".to_string(),
+            Verification::Invalid(reason) => format!("Неверная подпись: {}", reason),
+        }
+    }
+}
+
+// Проверка отчёта из файла .json или .html (отчёт встроен в страницу).
+// Встроенный отчёт ровно один: иначе подписанный блок можно подложить рядом с правленым
+pub fn verify_file(path: &Path, trusted: &TrustedKeys) -> Result<(ScanReport, Verification), String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let json = match text.match_indices(HTML_REPORT_START).count() {
+        0 => text.trim_start_matches('\u{feff}'),
+        1 => {
+            let start = text.find(HTML_REPORT_START).unwrap_or_default();
+            let body = &text[start + HTML_REPORT_START.len()..];
+            let end = body.find(HTML_REPORT_END)
+                .ok_or_else(|| format!("{}: не найден конец встроенного отчёта", path.display()))?;
+            &body[..end]
+        }
+        count => return Err(format!("{}: встроенных отчётов {}, ожидается один", path.display(), count)),
+    };
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let verification = verify_value(&value, trusted);
+    let report = serde_json::from_value(value)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((report, verification))
+}
+
+// Проверяем исходный JSON, а не ScanReport: лишние поля, дописанные игроком, тоже ломают подпись
+pub fn verify_value(value: &Value, trusted: &TrustedKeys) -> Verification {
+    let Some(block) = value.get("signature").filter(|v| !v.is_null()) else {
+        return Verification::Unsigned;
+    };
+    let block: ReportSignature = match serde_json::from_value(block.clone()) {
+        Ok(block) => block,
+        Err(e) => return Verification::Invalid(e.to_string()),
+    };
+    if block.algorithm != ALGORITHM {
+        return Verification::Invalid(format!("неизвестный алгоритм {}", block.algorithm));
+    }
+    let Some(key) = from_hex(&block.public_key)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok()) else {
+        return Verification::Invalid("открытый ключ".to_string());
+    };
+    let Some(signature) = from_hex(&block.signature)
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .map(|bytes| Signature::from_bytes(&bytes)) else {
+        return Verification::Invalid("подпись".to_string());
+    };
+
+    if key.verify_strict(&canonical_bytes(value), &signature).is_err() {
+        Verification::Altered
+    } else if trusted.contains(&block.public_key) {
+        Verification::Trusted
+    } else {
+        Verification::UnknownKey(block.public_key)
+    }
+}
+
+// Канонический вид: компактный JSON, ключи объектов по возрастанию, без поля signature
+// верхнего уровня. Не зависит от порядка полей в файле и от версии serde_json
+pub fn canonical_bytes(value: &Value) -> Vec<u8> {
+    let mut out = String::new();
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().filter(|k| *k != "signature").collect();
+            keys.sort();
+            write_object(map, &keys, &mut out);
+        }
+        other => write_canonical(other, &mut out),
+    }
+    out.into_bytes()
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            write_object(map, &keys, out);
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn write_object(map: &serde_json::Map<String, Value>, keys: &[&String], out: &mut String) {
+    out.push('{');
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&Value::String((*key).clone()).to_string());
+        out.push(':');
+        write_canonical(&map[key.as_str()], out);
+    }
+    out.push('}');
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.parent()?.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+    use super::*;
+    use crate::detector::CheatDetector;
+    use crate::report::ReportFormat;
+
+    fn signed_report(signer: &ReportSigner) -> ScanReport {
+        let mut report = ScanReport::new(&CheatDetector::new(), &[PathBuf::from("mods")], SystemTime::UNIX_EPOCH);
+        report.stats.total = 3;
+        report.stats.checked = 3;
+        report.finish(Duration::from_secs(2));
+        signer.sign(&mut report).unwrap();
+        report
+    }
+
+    fn verify_text(name: &str, text: &str, trusted: &TrustedKeys) -> Result<(ScanReport, Verification), String> {
+        let path = std::env::temp_dir().join(format!("sbcheats-{}-{}", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        let result = verify_file(&path, trusted);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn signed_report_verifies_only_with_trusted_key() {
+        let signer = ReportSigner::generate().unwrap();
+        let json = signed_report(&signer).render(ReportFormat::Json).unwrap();
+        let trusted = TrustedKeys { keys: vec![signer.public_key()] };
+
+        let (report, verification) = verify_text("trusted.json", &json, &trusted).unwrap();
+        assert_eq!(verification, Verification::Trusted);
+        assert_eq!(report.stats.checked, 3);
+
+        let (_, verification) = verify_text("unknown.json", &json, &TrustedKeys::default()).unwrap();
+        assert_eq!(verification, Verification::UnknownKey(signer.public_key()));
+    }
+
+    #[test]
+    fn edited_report_is_altered() {
+        let signer = ReportSigner::generate().unwrap();
+        let trusted = TrustedKeys { keys: vec![signer.public_key()] };
+        let mut value = serde_json::to_value(signed_report(&signer)).unwrap();
+        assert_eq!(verify_value(&value, &trusted), Verification::Trusted);
+        value["stats"]["checked"] = 30.into();
+        assert_eq!(verify_value(&value, &trusted), Verification::Altered);
+
+        value.as_object_mut().unwrap().remove("signature");
+        assert_eq!(verify_value(&value, &trusted), Verification::Unsigned);
+    }
+
+    #[test]
+    fn html_must_embed_exactly_one_report() {
+        let signer = ReportSigner::generate().unwrap();
+        let trusted = TrustedKeys { keys: vec![signer.public_key()] };
+        let html = signed_report(&signer).render(ReportFormat::Html).unwrap();
+        let (_, verification) = verify_text("single.html", &html, &trusted).unwrap();
+        assert_eq!(verification, Verification::Trusted);
+
+        // Подписанный блок старого отчёта перед правленым
+        let start = html.find(HTML_REPORT_START).unwrap();
+        let block = &html[start..start + html[start..].find(HTML_REPORT_END).unwrap() + HTML_REPORT_END.len()];
+        let doubled = format!("{}{}", block, html);
+        let error = verify_text("double.html", &doubled, &trusted).unwrap_err();
+        assert!(error.contains("встроенных отчётов 2"), "{}", error);
+    }
+}
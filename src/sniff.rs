@@ -0,0 +1,95 @@
+// sniff.rs - Поиск ZIP-архивов по содержимому, а не по расширению
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use crate::zipcheck::{find_eocd, EOCD_SIZE};
+
+pub const ZIP_MAGIC: &[u8; 4] = b"PK\x03\x04";
+// End of central directory + комментарий максимальной длины
+const MAX_TAIL: u64 = EOCD_SIZE as u64 + 65535;
+
+// Расширения, честно говорящие "это архив"
+const ARCHIVE_EXTENSIONS: &[&str] = &["jar", "zip", "litemod", "mcpack", "mrpack", "war", "ear"];
+// Менеджеры модов отключают мод суффиксом: mod.jar.disabled
+const DISABLED_SUFFIX: &str = ".disabled";
+
+// ZIP в начале файла или дописанный в конец другого файла.
+// Читаются только начало и хвост
+pub fn is_archive_file(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    let mut head = [0u8; 4];
+    file.read_exact(&mut head)?;
+    if &head == ZIP_MAGIC {
+        return Ok(true);
+    }
+
+    let tail_len = len.min(MAX_TAIL);
+    let mut tail = Vec::with_capacity(tail_len as usize);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    file.take(tail_len).read_to_end(&mut tail)?;
+    Ok(find_eocd(&tail).is_some_and(|eocd| eocd.cd_size > 0))
+}
+
+pub fn is_archive(data: &[u8]) -> bool {
+    let tail_start = data.len().saturating_sub(MAX_TAIL as usize);
+    data.starts_with(ZIP_MAGIC)
+        || find_eocd(&data[tail_start..]).is_some_and(|eocd| eocd.cd_size > 0)
+}
+
+// Причина, по которой архив считается замаскированным, или None
+pub fn disguise_reason(file_name: &str) -> Option<String> {
+    let lower = file_name.to_lowercase();
+    let name = lower.strip_suffix(DISABLED_SUFFIX).unwrap_or(&lower);
+    match name.rsplit_once('.') {
+        Some((_, ext)) if ARCHIVE_EXTENSIONS.contains(&ext) => None,
+        Some((_, ext)) => Some(format!("Расширение .{} скрывает ZIP-архив", ext)),
+        None => Some("ZIP-архив без расширения".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::jar;
+
+    #[test]
+    fn finds_archive_by_content() {
+        let data = jar(&[("a/B.class", b"\xCA\xFE\xBA\xBE")]);
+        assert!(is_archive(&data));
+
+        // jar, дописанный в конец картинки: заголовок не ZIP, но EOCD в хвосте
+        let mut appended = b"\x89PNG\r\n\x1a\n".to_vec();
+        appended.extend(vec![0u8; 4096]);
+        appended.extend(&data);
+        assert!(is_archive(&appended));
+
+        assert!(!is_archive(b"\x89PNG\r\n\x1a\n not an archive"));
+    }
+
+    #[test]
+    fn reads_only_head_and_tail_of_file() {
+        let mut appended = vec![0u8; 100_000];
+        appended.extend(jar(&[("a/B.class", b"\xCA\xFE\xBA\xBE")]));
+        let path = std::env::temp_dir().join(format!("sbcheats-{}-appended.dat", std::process::id()));
+        std::fs::write(&path, &appended).unwrap();
+        let found = is_archive_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(found);
+    }
+
+    #[test]
+    fn honest_extensions_are_not_disguise() {
+        for name in ["cheat.jar", "Cheat.JAR", "cheat.jar.disabled", "pack.zip", "pack.mrpack"] {
+            assert_eq!(disguise_reason(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn other_extensions_are_disguise() {
+        assert_eq!(disguise_reason("skin.png").as_deref(), Some("Расширение .png скрывает ZIP-архив"));
+        assert_eq!(disguise_reason("skin.png.disabled").as_deref(), Some("Расширение .png скрывает ZIP-архив"));
+        assert_eq!(disguise_reason("cache").as_deref(), Some("ZIP-архив без расширения"));
+    }
+}
@@ -0,0 +1,172 @@
+// testutil.rs - Сборка .class и jar в памяти для тестов
+// Помощники общие для тестов разных модулей, не каждый использует все
+#![allow(dead_code)]
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+// Значение элемента аннотации
+pub enum Element<'a> {
+    Str(&'a str),
+    Class(&'a str),
+    Array(Vec<Element<'a>>),
+}
+
+// Минимальный class file: constant pool, аннотации класса и методов
+pub struct ClassBuilder {
+    pool: Vec<Vec<u8>>,
+    this_class: u16,
+    class_annotations: Vec<Vec<u8>>,
+    methods: Vec<(u16, Vec<Vec<u8>>)>,
+}
+
+impl ClassBuilder {
+    pub fn new(name: &str) -> Self {
+        let mut builder = Self { pool: Vec::new(), this_class: 0, class_annotations: Vec::new(), methods: Vec::new() };
+        builder.this_class = builder.class(name);
+        builder
+    }
+
+    pub fn utf8(&mut self, text: &str) -> u16 {
+        let mut entry = vec![1];
+        entry.extend((text.len() as u16).to_be_bytes());
+        entry.extend(text.as_bytes());
+        self.push(entry)
+    }
+
+    pub fn class(&mut self, name: &str) -> u16 {
+        let index = self.utf8(name);
+        let mut entry = vec![7];
+        entry.extend(index.to_be_bytes());
+        self.push(entry)
+    }
+
+    pub fn string(&mut self, text: &str) -> u16 {
+        let index = self.utf8(text);
+        let mut entry = vec![8];
+        entry.extend(index.to_be_bytes());
+        self.push(entry)
+    }
+
+    pub fn method_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(10, owner, name, descriptor)
+    }
+
+    pub fn field_ref(&mut self, owner: &str, name: &str, descriptor: &str) -> u16 {
+        self.member_ref(9, owner, name, descriptor)
+    }
+
+    // Fieldref / Methodref: класс + NameAndType
+    fn member_ref(&mut self, tag: u8, owner: &str, name: &str, descriptor: &str) -> u16 {
+        let class = self.class(owner);
+        let mut name_and_type = vec![12];
+        name_and_type.extend(self.utf8(name).to_be_bytes());
+        name_and_type.extend(self.utf8(descriptor).to_be_bytes());
+        let name_and_type = self.push(name_and_type);
+        let mut entry = vec![tag];
+        entry.extend(class.to_be_bytes());
+        entry.extend(name_and_type.to_be_bytes());
+        self.push(entry)
+    }
+
+    fn push(&mut self, entry: Vec<u8>) -> u16 {
+        if let Some(i) = self.pool.iter().position(|e| *e == entry) {
+            return i as u16 + 1;
+        }
+        self.pool.push(entry);
+        self.pool.len() as u16
+    }
+
+    // annotation: type_index, num_pairs, пары name_index + element_value
+    pub fn annotation(&mut self, descriptor: &str, elements: &[(&str, Element)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(self.utf8(descriptor).to_be_bytes());
+        out.extend((elements.len() as u16).to_be_bytes());
+        for (name, value) in elements {
+            out.extend(self.utf8(name).to_be_bytes());
+            self.element(value, &mut out);
+        }
+        out
+    }
+
+    fn element(&mut self, value: &Element, out: &mut Vec<u8>) {
+        match value {
+            Element::Str(text) => {
+                out.push(b's');
+                out.extend(self.utf8(text).to_be_bytes());
+            }
+            Element::Class(name) => {
+                out.push(b'c');
+                out.extend(self.utf8(&format!("L{};", name)).to_be_bytes());
+            }
+            Element::Array(items) => {
+                out.push(b'[');
+                out.extend((items.len() as u16).to_be_bytes());
+                for item in items {
+                    self.element(item, out);
+                }
+            }
+        }
+    }
+
+    // Готовые байты annotation - для заведомо испорченных аннотаций
+    pub fn class_annotation(mut self, annotation: Vec<u8>) -> Self {
+        self.class_annotations.push(annotation);
+        self
+    }
+
+    pub fn method(mut self, name: &str, annotations: Vec<Vec<u8>>) -> Self {
+        let index = self.utf8(name);
+        self.utf8("()V");
+        self.methods.push((index, annotations));
+        self
+    }
+
+    pub fn build(mut self) -> Vec<u8> {
+        let attribute_name = self.utf8("RuntimeVisibleAnnotations");
+        let descriptor = self.utf8("()V");
+        let mut out = Vec::new();
+        out.extend(0xCAFE_BABEu32.to_be_bytes());
+        out.extend([0, 0, 0, 52]);
+        out.extend((self.pool.len() as u16 + 1).to_be_bytes());
+        for entry in &self.pool {
+            out.extend(entry);
+        }
+        out.extend([0, 0x21]);
+        out.extend(self.this_class.to_be_bytes());
+        out.extend([0, 0, 0, 0, 0, 0]); // super_class, interfaces, fields
+
+        out.extend((self.methods.len() as u16).to_be_bytes());
+        for (name, annotations) in &self.methods {
+            out.extend([0, 1]);
+            out.extend(name.to_be_bytes());
+            out.extend(descriptor.to_be_bytes());
+            write_annotations(&mut out, attribute_name, annotations);
+        }
+        write_annotations(&mut out, attribute_name, &self.class_annotations);
+        out
+    }
+}
+
+// attributes_count + один атрибут с аннотациями (или ни одного)
+fn write_annotations(out: &mut Vec<u8>, attribute_name: u16, annotations: &[Vec<u8>]) {
+    if annotations.is_empty() {
+        out.extend([0, 0]);
+        return;
+    }
+    let body: Vec<u8> = annotations.iter().flatten().copied().collect();
+    out.extend([0, 1]);
+    out.extend(attribute_name.to_be_bytes());
+    out.extend((body.len() as u32 + 2).to_be_bytes());
+    out.extend((annotations.len() as u16).to_be_bytes());
+    out.extend(body);
+}
+
+pub fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        writer.start_file(*name, FileOptions::default()).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
@@ -0,0 +1,326 @@
+// versions.rs - Версии в versions/<имя>/<имя>.json: чит-клиент может поставиться
+// как "версия" с чужим mainClass, своими библиотеками или пропатченным jar
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::errors::{ScanError, ScanErrorKind};
+use crate::integrity::{ExpectedHash, LibraryFile};
+
+// Точки входа ванили и загрузчиков
+const KNOWN_MAIN_CLASSES: &[&str] = &[
+    "net.minecraft.client.main.Main",
+    "net.minecraft.launchwrapper.Launch",
+    "net.fabricmc.loader.impl.launch.knot.KnotClient",
+    "net.fabricmc.loader.launch.knot.KnotClient",
+    "org.quiltmc.loader.impl.launch.knot.KnotClient",
+    "cpw.mods.modlauncher.Launcher",
+    "cpw.mods.bootstraplauncher.BootstrapLauncher",
+    "net.minecraftforge.bootstrap.ForgeBootstrap",
+    "net.neoforged.fml.startup.Client",
+    "io.github.zekerzhayard.forgewrapper.installer.Main",
+    "net.minecraft.client.Minecraft",
+];
+
+// Группы библиотек Mojang, загрузчиков, OptiFine и лаунчеров (authlib-injector, TLauncher)
+const KNOWN_LIBRARY_GROUPS: &[&str] = &[
+    "com.mojang", "net.minecraft", "org.lwjgl", "net.java.dev.jna", "net.java.jinput",
+    "net.java.jutils", "com.google", "org.apache", "commons-io", "commons-codec", "commons-logging",
+    "commons-lang", "io.netty", "it.unimi.dsi", "net.sf.jopt-simple", "org.slf4j",
+    "ca.weblite", "com.github.oshi", "oshi-project", "org.joml", "com.paulscode", "lzma",
+    "java3d", "net.sf.trove4j", "com.ibm", "org.jcraft", "tv.twitch", "com.typesafe",
+    "org.scala-lang", "net.fabricmc", "org.quiltmc", "org.ow2.asm",
+    "net.minecraftforge", "cpw.mods", "de.oceanlabs", "org.spongepowered", "net.neoforged",
+    "org.jline", "optifine", "net.jodah", "org.openjdk.nashorn", "com.electronwill.night-config",
+    "moe.yushi", "org.tlauncher", "io.github.zekerzhayard",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub name: String,
+    pub json_path: PathBuf,
+    // versions/<имя>/<имя>.jar, если есть
+    pub jar_path: Option<PathBuf>,
+    pub main_class: Option<String>,
+    pub inherits_from: Option<String>,
+    // Координаты group:artifact:version вне известных групп
+    pub unknown_libraries: Vec<String>,
+    // Их jar в libraries/, если скачаны
+    pub unknown_library_jars: Vec<PathBuf>,
+    // SHA-1 jar клиента из downloads.client (см. integrity)
+    pub client_hash: Option<ExpectedHash>,
+    // Скачанные библиотеки с SHA-1 из json
+    pub libraries: Vec<LibraryFile>,
+}
+
+impl VersionInfo {
+    pub fn has_custom_main(&self) -> bool {
+        self.main_class.as_deref().is_some_and(|main| !KNOWN_MAIN_CLASSES.contains(&main))
+    }
+
+    pub fn is_suspicious(&self) -> bool {
+        self.has_custom_main() || !self.unknown_libraries.is_empty()
+    }
+
+    // Причины для отчёта, самая важная - первой
+    pub fn reasons(&self) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.has_custom_main() {
+            reasons.push(format!("Нестандартный mainClass: {}", self.main_class.as_deref().unwrap_or_default()));
+        }
+        if !self.unknown_libraries.is_empty() {
+            reasons.push(format!("Неизвестные библиотеки: {}", self.unknown_libraries.join(", ")));
+        }
+        if let Some(ref parent) = self.inherits_from {
+            reasons.push(format!("Наследует версию {}", parent));
+        }
+        reasons
+    }
+}
+
+pub fn parse(json_path: &Path, libraries_dir: &Path) -> Result<VersionInfo, String> {
+    let text = std::fs::read_to_string(json_path)
+        .map_err(|e| format!("{}: {}", json_path.display(), e))?;
+    let json: Value = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("{}: {}", json_path.display(), e))?;
+
+    let name = json_path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let jar_path = json_path.with_extension("jar");
+
+    let source = format!("versions/{}/{}.json", name, name);
+    let client_hash = json.pointer("/downloads/client/sha1").and_then(Value::as_str).map(|sha1| ExpectedHash {
+        sha1: sha1.to_string(),
+        source: format!("{}: client", source),
+        client: true,
+    });
+
+    let mut unknown_libraries = Vec::new();
+    let mut unknown_library_jars = Vec::new();
+    let mut libraries = Vec::new();
+    for library in json.get("libraries").and_then(Value::as_array).into_iter().flatten() {
+        let Some(coordinate) = library.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        if let Some(file) = library_file(library, coordinate, libraries_dir) {
+            libraries.push(file);
+        }
+        let group = coordinate.split(':').next().unwrap_or_default();
+        let known = KNOWN_LIBRARY_GROUPS.iter()
+            .any(|known| group == *known || group.starts_with(&format!("{}.", known)));
+        if known {
+            continue;
+        }
+        unknown_libraries.push(coordinate.to_string());
+        if let Some(jar) = library_path(coordinate).map(|p| libraries_dir.join(p)).filter(|p| p.is_file()) {
+            unknown_library_jars.push(jar);
+        }
+    }
+
+    Ok(VersionInfo {
+        name,
+        json_path: json_path.to_path_buf(),
+        jar_path: jar_path.is_file().then_some(jar_path),
+        main_class: json.get("mainClass").and_then(Value::as_str).map(str::to_string),
+        inherits_from: json.get("inheritsFrom").and_then(Value::as_str).map(str::to_string),
+        unknown_libraries,
+        unknown_library_jars,
+        client_hash,
+        libraries,
+    })
+}
+
+// Все версии в <папка игры>/versions
+pub fn collect(game_dir: &Path) -> (Vec<VersionInfo>, Vec<ScanError>) {
+    let mut versions = Vec::new();
+    let mut errors = Vec::new();
+    let Ok(entries) = std::fs::read_dir(game_dir.join("versions")) else {
+        return (versions, errors);
+    };
+    let libraries_dir = game_dir.join("libraries");
+    for dir in entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.is_dir()) {
+        let Some(name) = dir.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let json_path = dir.join(format!("{}.json", name));
+        if !json_path.is_file() {
+            continue;
+        }
+        match parse(&json_path, &libraries_dir) {
+            Ok(version) => versions.push(version),
+            Err(e) => errors.push(ScanError::new(ScanErrorKind::Io, json_path.display().to_string(), e)),
+        }
+    }
+    versions.sort_by(|a, b| a.json_path.cmp(&b.json_path));
+    inherit_from_parents(&mut versions);
+    (versions, errors)
+}
+
+// inheritsFrom: чего нет в json версии, берётся у родителя - mainClass и SHA-1
+// клиента. Jar, скопированный из ванили и пропатченный, так сверяется с её хешем.
+// Библиотеки родителя проверяются вместе с ним самим
+fn inherit_from_parents(versions: &mut [VersionInfo]) {
+    for i in 0..versions.len() {
+        let mut parent_name = versions[i].inherits_from.clone();
+        // Цепочка родителей, но не дольше числа версий - на случай цикла
+        for _ in 0..versions.len() {
+            let Some(parent) = parent_name.and_then(|name| versions.iter().find(|v| v.name == name)) else {
+                break;
+            };
+            let main_class = parent.main_class.clone();
+            let client_hash = parent.client_hash.clone();
+            parent_name = parent.inherits_from.clone();
+
+            let version = &mut versions[i];
+            if version.main_class.is_none() {
+                version.main_class = main_class;
+            }
+            if version.client_hash.is_none() && version.jar_path.is_some() {
+                version.client_hash = client_hash;
+            }
+        }
+    }
+}
+
+// Mojang: downloads.artifact {path, sha1}; Fabric и Quilt: sha1 рядом с name.
+// Библиотеки без хеша или не скачанные (нативные под другую ОС) пропускаются
+fn library_file(library: &Value, coordinate: &str, libraries_dir: &Path) -> Option<LibraryFile> {
+    let artifact = library.pointer("/downloads/artifact");
+    let sha1 = artifact.and_then(|a| a.get("sha1"))
+        .or_else(|| library.get("sha1"))
+        .and_then(Value::as_str)?;
+    let relative = artifact.and_then(|a| a.get("path"))
+        .and_then(Value::as_str)
+        .map(PathBuf::from)
+        .or_else(|| library_path(coordinate))?;
+    let path = libraries_dir.join(relative);
+    path.is_file().then(|| LibraryFile {
+        path,
+        expected: ExpectedHash {
+            sha1: sha1.to_string(),
+            source: coordinate.to_string(),
+            client: false,
+        },
+    })
+}
+
+// "group:artifact:version[:classifier]" -> group/path/artifact/version/artifact-version[-classifier].jar
+pub fn library_path(coordinate: &str) -> Option<PathBuf> {
+    let parts: Vec<&str> = coordinate.split(':').collect();
+    let [group, artifact, version, rest @ ..] = parts.as_slice() else {
+        return None;
+    };
+    let mut file = format!("{}-{}", artifact, version);
+    if let Some(classifier) = rest.first() {
+        file.push('-');
+        file.push_str(classifier);
+    }
+    file.push_str(".jar");
+    Some(PathBuf::from(group.replace('.', "/")).join(artifact).join(version).join(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(name: &str, inherits_from: Option<&str>, main_class: Option<&str>) -> VersionInfo {
+        VersionInfo {
+            name: name.to_string(),
+            json_path: PathBuf::from(format!("versions/{}/{}.json", name, name)),
+            jar_path: None,
+            main_class: main_class.map(str::to_string),
+            inherits_from: inherits_from.map(str::to_string),
+            unknown_libraries: Vec::new(),
+            unknown_library_jars: Vec::new(),
+            client_hash: None,
+            libraries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn child_inherits_main_class_and_client_hash() {
+        let mut vanilla = version("1.20.1", None, Some("net.minecraft.client.main.Main"));
+        vanilla.client_hash = Some(ExpectedHash {
+            sha1: "abc".to_string(),
+            source: "versions/1.20.1/1.20.1.json: client".to_string(),
+            client: true,
+        });
+        let mut patched = version("1.20.1-patched", Some("1.20.1"), None);
+        patched.jar_path = Some(PathBuf::from("versions/1.20.1-patched/1.20.1-patched.jar"));
+        let fabric = version("fabric", Some("1.20.1-patched"), Some("net.fabricmc.loader.impl.launch.knot.KnotClient"));
+        let mut versions = vec![vanilla, patched, fabric];
+        inherit_from_parents(&mut versions);
+
+        assert_eq!(versions[1].main_class.as_deref(), Some("net.minecraft.client.main.Main"));
+        assert_eq!(versions[1].client_hash.as_ref().map(|h| h.sha1.as_str()), Some("abc"));
+        // Свой mainClass не заменяется, а без своего jar хеш клиента не нужен
+        assert_eq!(versions[2].main_class.as_deref(), Some("net.fabricmc.loader.impl.launch.knot.KnotClient"));
+        assert!(versions[2].client_hash.is_none());
+    }
+
+    #[test]
+    fn inheritance_cycle_terminates() {
+        let mut versions = vec![version("a", Some("b"), None), version("b", Some("a"), None)];
+        inherit_from_parents(&mut versions);
+        assert!(versions.iter().all(|v| v.main_class.is_none()));
+    }
+
+    #[test]
+    fn library_path_from_coordinate() {
+        assert_eq!(library_path("org.ow2.asm:asm:9.6"), Some(PathBuf::from("org/ow2/asm/asm/9.6/asm-9.6.jar")));
+        assert_eq!(library_path("org.lwjgl:lwjgl:3.3.1:natives-windows"),
+            Some(PathBuf::from("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-windows.jar")));
+        assert_eq!(library_path("no-version:artifact"), None);
+    }
+
+    #[test]
+    fn known_main_classes_are_not_custom() {
+        let fabric = version("fabric", Some("1.20.1"), Some("net.fabricmc.loader.impl.launch.knot.KnotClient"));
+        assert!(!fabric.is_suspicious());
+        let cheat = version("cheat", Some("1.20.1"), Some("me.cheat.Main"));
+        assert!(cheat.is_suspicious());
+        assert_eq!(cheat.reasons(), ["Нестандартный mainClass: me.cheat.Main", "Наследует версию 1.20.1"]);
+    }
+
+    #[test]
+    fn parses_version_json() {
+        let game = std::env::temp_dir().join(format!("sbcheats-{}-versions", std::process::id()));
+        let dir = game.join("versions/cheat");
+        let libraries = game.join("libraries");
+        std::fs::create_dir_all(&dir).unwrap();
+        for jar in ["org/ow2/asm/asm/9.6/asm-9.6.jar", "me/cheat/core/1.0/core-1.0.jar", "com/mojang/brigadier/1.1.8/b.jar"] {
+            std::fs::create_dir_all(libraries.join(jar).parent().unwrap()).unwrap();
+            std::fs::write(libraries.join(jar), b"jar").unwrap();
+        }
+        std::fs::write(dir.join("cheat.jar"), b"jar").unwrap();
+        std::fs::write(dir.join("cheat.json"), "\u{feff}".to_string() + &serde_json::json!({
+            "mainClass": "net.minecraft.client.main.Main",
+            "downloads": {"client": {"sha1": "C1"}},
+            "libraries": [
+                {"name": "com.mojang:brigadier:1.1.8",
+                    "downloads": {"artifact": {"path": "com/mojang/brigadier/1.1.8/b.jar", "sha1": "B1"}}},
+                {"name": "org.ow2.asm:asm:9.6", "sha1": "A1"},
+                {"name": "me.cheat:core:1.0"},
+                {"name": "com.mojangcheat:fake:1.0"},
+                {"name": "org.lwjgl:lwjgl:3.3.1:natives-macos", "sha1": "N1"}
+            ]
+        }).to_string()).unwrap();
+
+        let (versions, errors) = collect(&game);
+        std::fs::remove_dir_all(&game).unwrap();
+
+        assert!(errors.is_empty());
+        let version = &versions[0];
+        assert_eq!(version.name, "cheat");
+        assert_eq!(version.jar_path, Some(dir.join("cheat.jar")));
+        assert_eq!(version.client_hash.as_ref().map(|h| (h.sha1.as_str(), h.client)), Some(("C1", true)));
+        // Группа известна целиком или по префиксу с точкой, но не по началу имени
+        assert_eq!(version.unknown_libraries, ["me.cheat:core:1.0", "com.mojangcheat:fake:1.0"]);
+        assert_eq!(version.unknown_library_jars, [libraries.join("me/cheat/core/1.0/core-1.0.jar")]);
+        // Хеши - у скачанных библиотек: путь из downloads.artifact или из координат
+        let hashes: Vec<(&str, &str)> = version.libraries.iter()
+            .map(|l| (l.expected.source.as_str(), l.expected.sha1.as_str()))
+            .collect();
+        assert_eq!(hashes, [("com.mojang:brigadier:1.1.8", "B1"), ("org.ow2.asm:asm:9.6", "A1")]);
+    }
+}
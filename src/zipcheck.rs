@@ -0,0 +1,512 @@
+// zipcheck.rs - Проверка структуры ZIP и восстановление по локальным заголовкам
+//
+// JVM находит классы по central directory (ZipFile) или читает архив потоком
+// по локальным заголовкам (JarInputStream). Авторы читов ломают или подделывают
+// central directory, чтобы инструменты пропускали jar, а игра всё равно его грузила
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Cursor, Read, Write};
+use flate2::read::DeflateDecoder;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const LOCAL_MAGIC: &[u8; 4] = b"PK\x03\x04";
+const CENTRAL_MAGIC: &[u8; 4] = b"PK\x01\x02";
+const EOCD_MAGIC: &[u8; 4] = b"PK\x05\x06";
+const DESCRIPTOR_MAGIC: &[u8; 4] = b"PK\x07\x08";
+const LOCAL_HEADER_SIZE: usize = 30;
+const CENTRAL_HEADER_SIZE: usize = 46;
+pub const EOCD_SIZE: usize = 22;
+// Бит 3 флагов: размеры записаны после данных (data descriptor)
+const FLAG_DESCRIPTOR: u16 = 0x08;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+// Маркер ZIP64 - структура таких архивов здесь не разбирается
+const ZIP64_MARKER: u32 = 0xFFFF_FFFF;
+
+// Сколько имён показывать в одной строке отчёта
+const SHOWN_NAMES: usize = 3;
+pub struct Eocd {
+    // Позиция записи внутри переданного среза
+    pub pos: usize,
+    pub cd_size: u32,
+    pub cd_offset: u32,
+}
+
+// Ищем end of central directory с конца. Запись засчитывается, только если
+// длина комментария точно доходит до конца данных - случайные "PK\5\6" в
+// картинках и звуках так не совпадут
+pub fn find_eocd(tail: &[u8]) -> Option<Eocd> {
+    let last = tail.len().checked_sub(EOCD_SIZE)?;
+    (0..=last).rev().find_map(|pos| {
+        let eocd = &tail[pos..pos + EOCD_SIZE];
+        if &eocd[..4] != EOCD_MAGIC {
+            return None;
+        }
+        let comment_len = u16_at(eocd, 20)? as usize;
+        (pos + EOCD_SIZE + comment_len == tail.len()).then(|| Eocd {
+            pos,
+            cd_size: u32_at(eocd, 12).unwrap_or(0),
+            cd_offset: u32_at(eocd, 16).unwrap_or(0),
+        })
+    })
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Option<u16> {
+    let bytes = data.get(pos..pos + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Option<u32> {
+    let bytes = data.get(pos..pos + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+struct CentralEntry {
+    name: Vec<u8>,
+    // Абсолютное смещение локального заголовка в данных
+    local_offset: usize,
+    compressed_size: usize,
+    // Размеры или смещение вынесены в extra-поле ZIP64
+    zip64: bool,
+}
+
+struct LocalHeader {
+    name: Vec<u8>,
+    flags: u16,
+    method: u16,
+    compressed_size: u32,
+    data_start: usize,
+}
+
+fn read_local_header(data: &[u8], offset: usize) -> Option<LocalHeader> {
+    if data.get(offset..offset + 4)? != LOCAL_MAGIC {
+        return None;
+    }
+    let name_len = u16_at(data, offset + 26)? as usize;
+    let extra_len = u16_at(data, offset + 28)? as usize;
+    let name_start = offset + LOCAL_HEADER_SIZE;
+    let data_start = name_start + name_len + extra_len;
+    if data_start > data.len() {
+        return None;
+    }
+    Some(LocalHeader {
+        name: data[name_start..name_start + name_len].to_vec(),
+        flags: u16_at(data, offset + 6)?,
+        method: u16_at(data, offset + 8)?,
+        compressed_size: u32_at(data, offset + 18)?,
+        data_start,
+    })
+}
+
+// None - каталог повреждён
+fn read_central_directory(data: &[u8], eocd: &Eocd, archive_offset: usize) -> Option<Vec<CentralEntry>> {
+    let mut pos = archive_offset + eocd.cd_offset as usize;
+    let end = pos.checked_add(eocd.cd_size as usize)?;
+    if end > data.len() {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    while pos < end {
+        if data.get(pos..pos + 4)? != CENTRAL_MAGIC {
+            return None;
+        }
+        let compressed_size = u32_at(data, pos + 20)?;
+        let uncompressed_size = u32_at(data, pos + 24)?;
+        let local_offset = u32_at(data, pos + 42)?;
+        let zip64 = [compressed_size, uncompressed_size, local_offset].contains(&ZIP64_MARKER);
+        let name_len = u16_at(data, pos + 28)? as usize;
+        let extra_len = u16_at(data, pos + 30)? as usize;
+        let comment_len = u16_at(data, pos + 32)? as usize;
+        let name_start = pos + CENTRAL_HEADER_SIZE;
+        entries.push(CentralEntry {
+            name: data.get(name_start..name_start + name_len)?.to_vec(),
+            local_offset: archive_offset + local_offset as usize,
+            compressed_size: compressed_size as usize,
+            zip64,
+        });
+        pos = name_start + name_len + extra_len + comment_len;
+    }
+    Some(entries)
+}
+
+struct LocalEntry {
+    offset: usize,
+    header: LocalHeader,
+    compressed_size: usize,
+}
+
+// Размер сжатых данных: из заголовка, из central directory или, если записан
+// data descriptor, - по фактическому концу deflate-потока / следующей сигнатуре
+fn local_data_size(
+    data: &[u8],
+    offset: usize,
+    header: &LocalHeader,
+    central_sizes: &BTreeMap<usize, usize>,
+    budget: &mut u64,
+) -> Option<usize> {
+    if header.flags & FLAG_DESCRIPTOR == 0 || header.compressed_size != 0 {
+        if header.compressed_size == ZIP64_MARKER {
+            return None;
+        }
+        return Some(header.compressed_size as usize);
+    }
+    if let Some(&size) = central_sizes.get(&offset) {
+        return Some(size);
+    }
+
+    let rest = &data[header.data_start..];
+    if header.method == METHOD_DEFLATED {
+        let mut decoder = DeflateDecoder::new(rest);
+        let inflated = std::io::copy(&mut (&mut decoder).take(*budget), &mut std::io::sink()).ok()?;
+        *budget -= inflated;
+        return Some(decoder.total_in() as usize);
+    }
+    let next = rest.windows(4).position(|w| w == LOCAL_MAGIC || w == CENTRAL_MAGIC || w == EOCD_MAGIC)?;
+    // Сигнатура data descriptor + crc + два размера
+    let descriptor = if next >= 16 && &rest[next - 16..next - 12] == DESCRIPTOR_MAGIC { 16 } else { 12 };
+    next.checked_sub(descriptor)
+}
+
+fn data_descriptor_size(data: &[u8], pos: usize, header: &LocalHeader) -> usize {
+    if header.flags & FLAG_DESCRIPTOR == 0 {
+        0
+    } else if data.get(pos..pos + 4) == Some(DESCRIPTOR_MAGIC) {
+        16
+    } else {
+        12
+    }
+}
+
+// Проход по локальным заголовкам подряд, как это делает JarInputStream.
+// Мусор между записями пропускается поиском следующей сигнатуры
+fn walk_local_headers(
+    data: &[u8],
+    start: usize,
+    central_sizes: &BTreeMap<usize, usize>,
+    budget: &mut u64,
+) -> Vec<LocalEntry> {
+    let mut entries = Vec::new();
+    let mut pos = start;
+
+    while pos + 4 <= data.len() {
+        let Some(header) = read_local_header(data, pos) else {
+            let magic = &data[pos..pos + 4];
+            if magic == CENTRAL_MAGIC || magic == EOCD_MAGIC {
+                break;
+            }
+            match data[pos + 1..].windows(4).position(|w| w == LOCAL_MAGIC) {
+                Some(next) => {
+                    pos += 1 + next;
+                    continue;
+                }
+                None => break,
+            }
+        };
+        let Some(size) = local_data_size(data, pos, &header, central_sizes, budget) else { break };
+        let data_end = header.data_start.saturating_add(size);
+        if data_end > data.len() {
+            break;
+        }
+        let next = data_end + data_descriptor_size(data, data_end, &header);
+        entries.push(LocalEntry { offset: pos, header, compressed_size: size });
+        pos = next;
+    }
+    entries
+}
+
+fn names_preview(names: &[String]) -> String {
+    let mut text = names.iter().take(SHOWN_NAMES).cloned().collect::<Vec<_>>().join(", ");
+    if names.len() > SHOWN_NAMES {
+        text.push_str(&format!(" и ещё {}", names.len() - SHOWN_NAMES));
+    }
+    text
+}
+
+fn display_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn duplicates<'a>(names: impl Iterator<Item = &'a [u8]>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    let mut repeated = BTreeSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            repeated.insert(display_name(name));
+        }
+    }
+    repeated.into_iter().collect()
+}
+
+#[derive(Debug, Default)]
+pub struct ZipStructure {
+    // Признаки ручной правки архива, пусто - структура в порядке
+    pub tampering: Vec<String>,
+    // Архив, пересобранный из локальных заголовков: его и нужно анализировать,
+    // если central directory отсутствует или расходится с содержимым
+    pub recovered: Option<Vec<u8>>,
+    // Имена из central directory, которых нет среди локальных записей
+    pub central_only: Vec<String>,
+    // Распаковка при разборе упёрлась в лимит - восстановлено не всё
+    pub budget_exceeded: bool,
+    // Архив ZIP64: формат законный, но его структура здесь не проверяется
+    pub zip64: bool,
+}
+
+// max_uncompressed - сколько всего байт можно распаковать при разборе
+pub fn inspect(data: &[u8], max_uncompressed: u64) -> ZipStructure {
+    let mut structure = ZipStructure::default();
+    let mut budget = max_uncompressed;
+
+    let tail_start = data.len().saturating_sub(EOCD_SIZE + u16::MAX as usize);
+    let eocd = find_eocd(&data[tail_start..]);
+    let mut central = None;
+    let mut archive_offset = None;
+
+    if let Some(ref eocd) = eocd {
+        if eocd.cd_offset == ZIP64_MARKER || eocd.cd_size == ZIP64_MARKER {
+            structure.zip64 = true;
+            return structure;
+        }
+        let eocd_pos = tail_start + eocd.pos;
+        archive_offset = eocd_pos
+            .checked_sub(eocd.cd_size as usize)
+            .and_then(|p| p.checked_sub(eocd.cd_offset as usize));
+        central = archive_offset.and_then(|offset| read_central_directory(data, eocd, offset));
+    }
+    if central.iter().flatten().any(|entry| entry.zip64) {
+        structure.zip64 = true;
+        return structure;
+    }
+
+    // Смещению из каталога верим, только если сам каталог читается
+    let first_local = data.windows(4).position(|w| w == LOCAL_MAGIC);
+    let start = if central.is_some() { archive_offset } else { first_local };
+    let Some(start) = start else {
+        // Не ZIP вовсе
+        return structure;
+    };
+
+    match (&eocd, &central) {
+        (None, _) => structure.tampering.push("Central directory отсутствует".to_string()),
+        (Some(_), None) => structure.tampering.push("Central directory повреждён".to_string()),
+        _ => {}
+    }
+    if start > 0 {
+        structure.tampering.push(format!("Данные перед архивом: {} байт", start));
+    }
+
+    let central_sizes: BTreeMap<usize, usize> = central.iter()
+        .flatten()
+        .map(|e| (e.local_offset, e.compressed_size))
+        .collect();
+    let locals = walk_local_headers(data, start, &central_sizes, &mut budget);
+    if locals.is_empty() && central.is_none() {
+        return structure;
+    }
+
+    let local_duplicates = duplicates(locals.iter().map(|e| e.header.name.as_slice()));
+    if !local_duplicates.is_empty() {
+        structure.tampering.push(format!("Повторяющиеся записи: {}", names_preview(&local_duplicates)));
+    }
+
+    let mut need_recovery = central.is_none();
+
+    if let Some(ref central) = central {
+        let central_duplicates = duplicates(central.iter().map(|e| e.name.as_slice()));
+        if !central_duplicates.is_empty() && central_duplicates != local_duplicates {
+            structure.tampering.push(format!("Повторяющиеся записи в каталоге: {}", names_preview(&central_duplicates)));
+        }
+
+        // Имя в каталоге должно совпадать с именем в локальном заголовке по его смещению
+        let mut mismatched = Vec::new();
+        for entry in central {
+            match read_local_header(data, entry.local_offset) {
+                Some(local) if local.name == entry.name => {}
+                Some(local) => mismatched.push(format!("{} ≠ {}", display_name(&entry.name), display_name(&local.name))),
+                None => mismatched.push(format!("{} → нет заголовка", display_name(&entry.name))),
+            }
+        }
+        if !mismatched.is_empty() {
+            structure.tampering.push(format!("Каталог расходится с локальными заголовками: {}", names_preview(&mismatched)));
+            need_recovery = true;
+        }
+
+        // Записи, которых каталог не видит, но видит потоковое чтение
+        let referenced: BTreeSet<usize> = central.iter().map(|e| e.local_offset).collect();
+        let hidden: Vec<String> = locals.iter()
+            .filter(|e| !referenced.contains(&e.offset))
+            .map(|e| display_name(&e.header.name))
+            .collect();
+        if !hidden.is_empty() {
+            structure.tampering.push(format!("Записи вне central directory: {}", names_preview(&hidden)));
+            need_recovery = true;
+        }
+
+        // Пересекающиеся записи: данные одной начинаются внутри другой
+        let mut ranges: Vec<(usize, usize, &[u8])> = central.iter()
+            .filter_map(|e| {
+                let local = read_local_header(data, e.local_offset)?;
+                Some((e.local_offset, local.data_start + e.compressed_size, e.name.as_slice()))
+            })
+            .collect();
+        ranges.sort();
+        let overlapping: Vec<String> = ranges.windows(2)
+            .filter(|pair| pair[1].0 < pair[0].1)
+            .map(|pair| format!("{} / {}", display_name(pair[0].2), display_name(pair[1].2)))
+            .collect();
+        if !overlapping.is_empty() {
+            structure.tampering.push(format!("Пересекающиеся записи: {}", names_preview(&overlapping)));
+        }
+
+        if need_recovery {
+            let local_names: BTreeSet<&[u8]> = locals.iter().map(|e| e.header.name.as_slice()).collect();
+            structure.central_only = central.iter()
+                .filter(|e| !local_names.contains(e.name.as_slice()))
+                .map(|e| display_name(&e.name))
+                .collect();
+        }
+    }
+
+    if need_recovery {
+        structure.recovered = rebuild(data, &locals, &mut budget);
+    }
+    structure.budget_exceeded = budget == 0;
+    structure
+}
+
+fn decompress(data: &[u8], entry: &LocalEntry, budget: &mut u64) -> Option<Vec<u8>> {
+    let raw = data.get(entry.header.data_start..entry.header.data_start + entry.compressed_size)?;
+    let mut out = Vec::new();
+    match entry.header.method {
+        METHOD_STORED => out.extend_from_slice(&raw[..raw.len().min(*budget as usize)]),
+        METHOD_DEFLATED => {
+            DeflateDecoder::new(raw).take(*budget).read_to_end(&mut out).ok()?;
+        }
+        _ => return None,
+    }
+    *budget -= out.len() as u64;
+    Some(out)
+}
+
+// Чистый архив из того, что удалось прочитать по локальным заголовкам.
+// При повторах остаётся первая запись - её увидит потоковое чтение
+fn rebuild(data: &[u8], locals: &[LocalEntry], budget: &mut u64) -> Option<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut written = BTreeSet::new();
+
+    for entry in locals {
+        let name = display_name(&entry.header.name);
+        if !written.insert(name.clone()) {
+            continue;
+        }
+        if name.ends_with('/') {
+            writer.add_directory(name, options).ok()?;
+            continue;
+        }
+        if *budget == 0 {
+            break;
+        }
+        let Some(content) = decompress(data, entry, budget) else { continue };
+        writer.start_file(name, options).ok()?;
+        writer.write_all(&content).ok()?;
+    }
+
+    writer.finish().ok().map(Cursor::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::jar;
+
+    const LIMIT: u64 = 1 << 20;
+
+    fn sample() -> Vec<u8> {
+        jar(&[("a/A.class", b"first"), ("a/B.class", b"second")])
+    }
+
+    // Имена одной длины: заменяем n-е вхождение, смещения не съезжают
+    fn rename(data: &mut [u8], from: &[u8], to: &[u8], occurrence: usize) {
+        let pos = data.windows(from.len())
+            .enumerate()
+            .filter(|(_, w)| *w == from)
+            .nth(occurrence)
+            .unwrap().0;
+        data[pos..pos + to.len()].copy_from_slice(to);
+    }
+
+    fn names(archive: &[u8]) -> Vec<String> {
+        let archive = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(str::to_string).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn clean_archive_has_no_tampering() {
+        let structure = inspect(&sample(), LIMIT);
+        assert!(structure.tampering.is_empty());
+        assert!(structure.recovered.is_none());
+    }
+
+    #[test]
+    fn reports_prepended_data() {
+        let mut data = vec![0u8; 100];
+        data.extend(sample());
+        let structure = inspect(&data, LIMIT);
+        assert_eq!(structure.tampering, ["Данные перед архивом: 100 байт"]);
+        assert!(structure.recovered.is_none());
+    }
+
+    #[test]
+    fn reports_duplicate_entries() {
+        let mut data = sample();
+        // Локальный заголовок и запись каталога
+        rename(&mut data, b"a/B.class", b"a/A.class", 0);
+        rename(&mut data, b"a/B.class", b"a/A.class", 0);
+        let structure = inspect(&data, LIMIT);
+        assert_eq!(structure.tampering, ["Повторяющиеся записи: a/A.class"]);
+    }
+
+    #[test]
+    fn recovers_without_central_directory() {
+        let data = sample();
+        let eocd = find_eocd(&data).unwrap();
+        let structure = inspect(&data[..eocd.cd_offset as usize], LIMIT);
+        assert_eq!(structure.tampering, ["Central directory отсутствует"]);
+        assert_eq!(names(&structure.recovered.unwrap()), ["a/A.class", "a/B.class"]);
+    }
+
+    #[test]
+    fn recovers_when_catalog_lies() {
+        let mut data = sample();
+        // Второе вхождение - запись каталога, локальный заголовок не тронут
+        rename(&mut data, b"a/B.class", b"a/C.class", 1);
+        let structure = inspect(&data, LIMIT);
+        assert_eq!(structure.tampering, ["Каталог расходится с локальными заголовками: a/C.class ≠ a/B.class"]);
+        assert_eq!(structure.central_only, ["a/C.class"]);
+        assert_eq!(names(&structure.recovered.unwrap()), ["a/A.class", "a/B.class"]);
+    }
+
+    #[test]
+    fn zip64_is_unsupported_not_tampered() {
+        let mut data = sample();
+        // Размеры в каталоге как у записи больше 4 GB: настоящие лежат в extra-поле ZIP64
+        let pos = data.windows(4).position(|w| w == CENTRAL_MAGIC).unwrap();
+        data[pos + 20..pos + 28].copy_from_slice(&[0xFF; 8]);
+        let structure = inspect(&data, LIMIT);
+        assert!(structure.zip64);
+        assert!(structure.tampering.is_empty());
+        assert!(structure.recovered.is_none());
+    }
+
+    #[test]
+    fn recovery_stops_at_budget() {
+        let data = sample();
+        let eocd = find_eocd(&data).unwrap();
+        let structure = inspect(&data[..eocd.cd_offset as usize], 3);
+        assert!(structure.budget_exceeded);
+    }
+}